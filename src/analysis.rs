@@ -0,0 +1,274 @@
+// analysis of a position: top root moves of MCTS and principal variation
+// Values are given from perspective of player to move at root: 1.0 win, 0.5 tie, 0.0 loss.
+
+use super::{format_action, player_to_notation, UltTTT, UltTTTMCTSGame, UltTTTMove};
+use my_lib::my_mcts::{
+    ExpansionPolicy, Heuristic, MCTSAlgo, MCTSConfig, MCTSGame, MCTSNode, MCTSTree, PlainMCTS,
+    SimulationPolicy, TranspositionTable, UCTPolicy, UTCCache,
+};
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+use serde::{Serialize, Serializer};
+
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnalysisBudget {
+    Time(Duration),
+    Iterations(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MoveAnalysis {
+    #[serde(rename = "action", serialize_with = "serialize_action")]
+    pub mv: UltTTTMove,
+    pub visits: usize,
+    // mean value of simulations through move
+    pub mean_value: f32,
+    // heuristic value of move, which guides progressive widening
+    pub prior: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PositionAnalysis {
+    pub notation: String,
+    #[serde(serialize_with = "serialize_player")]
+    pub player_to_move: TicTacToeStatus,
+    pub iterations: usize,
+    pub time_ms: f64,
+    // most visited root moves in descending order of visits
+    pub top_moves: Vec<MoveAnalysis>,
+    // sequence of most visited moves starting at root
+    #[serde(serialize_with = "serialize_actions")]
+    pub principal_variation: Vec<UltTTTMove>,
+}
+
+impl Display for PositionAnalysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "position: {}", self.notation)?;
+        writeln!(
+            f,
+            "player to move: {}",
+            player_to_notation(self.player_to_move)
+        )?;
+        writeln!(
+            f,
+            "iterations: {} in {:.0} ms",
+            self.iterations, self.time_ms
+        )?;
+        writeln!(f, "move   visits   value   prior")?;
+        for move_analysis in self.top_moves.iter() {
+            writeln!(
+                f,
+                "{:<5} {:>7} {:>7.3} {:>7.3}",
+                format_action(move_analysis.mv),
+                move_analysis.visits,
+                move_analysis.mean_value,
+                move_analysis.prior
+            )?;
+        }
+        let principal_variation: Vec<String> = self
+            .principal_variation
+            .iter()
+            .map(|mv| format_action(*mv))
+            .collect();
+        write!(f, "pv: {}", principal_variation.join(", "))
+    }
+}
+
+pub trait RootAnalysis {
+    // all expanded moves of root in descending order of visits
+    fn root_moves(&mut self) -> Vec<MoveAnalysis>;
+    fn principal_variation(&self) -> Vec<UltTTTMove>;
+}
+
+impl<H, MC, UC, TT, UP, EP, SP> RootAnalysis
+    for PlainMCTS<UltTTTMCTSGame, H, MC, UC, TT, UP, EP, SP>
+where
+    H: Heuristic<UltTTTMCTSGame>,
+    MC: MCTSConfig<TicTacToeStatus>,
+    UC: UTCCache<UltTTTMCTSGame, UP, MC>,
+    TT: TranspositionTable<UltTTT, usize>,
+    UP: UCTPolicy<UltTTTMCTSGame, MC>,
+    EP: ExpansionPolicy<UltTTTMCTSGame, H, MC>,
+    SP: SimulationPolicy<UltTTTMCTSGame, H, MC>,
+{
+    fn root_moves(&mut self) -> Vec<MoveAnalysis> {
+        let Some(root_id) = self.tree.root_id() else {
+            return Vec::new();
+        };
+        let root_state = *self.tree.get_node(root_id).get_state();
+        let player_to_move = UltTTTMCTSGame::current_player(&root_state);
+        let mut root_moves: Vec<MoveAnalysis> = Vec::new();
+        for (child_id, mv) in self.tree.get_children(root_id).iter() {
+            let child = self.tree.get_node(*child_id);
+            let visits = child.get_visits();
+            // accumulated value is given from perspective of First
+            let mean_value = if visits == 0 {
+                0.5
+            } else {
+                child.get_accumulated_value() / visits as f32
+            };
+            let mean_value = if player_to_move == TicTacToeStatus::First {
+                mean_value
+            } else {
+                1.0 - mean_value
+            };
+            let prior = H::evaluate_move(
+                &root_state,
+                mv,
+                &mut self.game_cache,
+                &mut self.heuristic_cache,
+                &self.heuristic_config,
+            );
+            root_moves.push(MoveAnalysis {
+                mv: *mv,
+                visits,
+                mean_value,
+                prior,
+            });
+        }
+        // moves with equal visits keep order of children
+        root_moves.sort_by_key(|move_analysis| std::cmp::Reverse(move_analysis.visits));
+        root_moves
+    }
+    fn principal_variation(&self) -> Vec<UltTTTMove> {
+        let mut principal_variation = Vec::new();
+        let Some(mut node_id) = self.tree.root_id() else {
+            return principal_variation;
+        };
+        // every move occupies a cell, therefore the path ends after at most 81 moves
+        while let Some((child_id, mv)) = self
+            .tree
+            .get_children(node_id)
+            .iter()
+            .filter(|(child_id, _)| self.tree.get_node(*child_id).get_visits() > 0)
+            .max_by_key(|(child_id, _)| self.tree.get_node(*child_id).get_visits())
+        {
+            principal_variation.push(*mv);
+            node_id = *child_id;
+        }
+        principal_variation
+    }
+}
+
+// searches state from scratch within budget and reports top_k root moves
+pub fn analyze_position<A, H>(
+    mcts: &mut A,
+    state: &UltTTT,
+    budget: AnalysisBudget,
+    top_k: usize,
+) -> PositionAnalysis
+where
+    H: Heuristic<UltTTTMCTSGame>,
+    A: MCTSAlgo<UltTTTMCTSGame, H> + RootAnalysis,
+{
+    mcts.reset_root(state);
+    let start = Instant::now();
+    let mut iterations = 0;
+    if state.get_status() == TicTacToeStatus::Vacant {
+        loop {
+            let done = match budget {
+                AnalysisBudget::Time(time) => start.elapsed() >= time,
+                AnalysisBudget::Iterations(max_iterations) => iterations >= max_iterations,
+            };
+            if done {
+                break;
+            }
+            mcts.iterate();
+            iterations += 1;
+        }
+    }
+    let mut top_moves = mcts.root_moves();
+    top_moves.truncate(top_k);
+    PositionAnalysis {
+        notation: state.to_notation(),
+        player_to_move: UltTTTMCTSGame::current_player(state),
+        iterations,
+        time_ms: start.elapsed().as_secs_f64() * 1_000.0,
+        top_moves,
+        principal_variation: mcts.principal_variation(),
+    }
+}
+
+fn serialize_action<S: Serializer>(mv: &UltTTTMove, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format_action(*mv))
+}
+
+fn serialize_actions<S: Serializer>(
+    moves: &[UltTTTMove],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(moves.iter().map(|mv| format_action(*mv)))
+}
+
+fn serialize_player<S: Serializer>(
+    player: &TicTacToeStatus,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&player_to_notation(*player).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UltTTTMCTSConfig;
+    use my_lib::my_mcts::{
+        CachedUTC, DefaultSimulationPolicy, DynamicC, ExpandAll, NoHeuristic, NoTranspositionTable,
+    };
+
+    type TestMCTS = PlainMCTS<
+        UltTTTMCTSGame,
+        NoHeuristic,
+        UltTTTMCTSConfig,
+        CachedUTC,
+        NoTranspositionTable,
+        DynamicC,
+        ExpandAll,
+        DefaultSimulationPolicy,
+    >;
+
+    // First won both left mini boards of top row and wins game with "0 8" in top right mini board
+    // (notation index 6)
+    const WIN_IN_ONE: &str = "XXXXXXXX1/9/9/OO1OO1OO1/O2O5/9/9/9/9 X O 6";
+
+    #[test]
+    fn test_analysis_finds_winning_move() {
+        let state = UltTTT::from_notation(WIN_IN_ONE).unwrap();
+        let mut mcts = TestMCTS::new(UltTTTMCTSConfig::default(), NoHeuristic {}, 10_000);
+        let analysis = analyze_position(&mut mcts, &state, AnalysisBudget::Iterations(2_000), 3);
+        assert_eq!(analysis.iterations, 2_000);
+        assert_eq!(analysis.player_to_move, TicTacToeStatus::First);
+        assert_eq!(analysis.top_moves.len(), 3);
+        let best = &analysis.top_moves[0];
+        assert_eq!(format_action(best.mv), "0 8");
+        assert_eq!(best.mean_value, 1.0);
+        assert!(analysis
+            .top_moves
+            .windows(2)
+            .all(|pair| pair[0].visits >= pair[1].visits));
+        // game ends with winning move
+        assert_eq!(analysis.principal_variation, vec![best.mv]);
+
+        let json = serde_json::to_value(&analysis).unwrap();
+        assert_eq!(json["top_moves"][0]["action"], "0 8");
+        assert_eq!(json["principal_variation"][0], "0 8");
+        assert_eq!(json["player_to_move"], "X");
+        assert!(analysis.to_string().contains("pv: 0 8"));
+    }
+
+    #[test]
+    fn test_analysis_from_perspective_of_second() {
+        // Second wins with "0 8": values are given from perspective of Second
+        let state = UltTTT::from_notation("OOOOOOOO1/9/9/XX1XX1XX1/X2X5/9/4X4/9/9 O X 6").unwrap();
+        let mut mcts = TestMCTS::new(UltTTTMCTSConfig::default(), NoHeuristic {}, 10_000);
+        let budget = AnalysisBudget::Time(Duration::from_millis(20));
+        let analysis = analyze_position(&mut mcts, &state, budget, 10);
+        assert_eq!(analysis.player_to_move, TicTacToeStatus::Second);
+        // top right mini board has 7 vacant cells
+        assert_eq!(analysis.top_moves.len(), 7);
+        assert_eq!(format_action(analysis.top_moves[0].mv), "0 8");
+        assert_eq!(analysis.top_moves[0].mean_value, 1.0);
+        assert!(analysis.time_ms >= 20.0);
+    }
+}
@@ -0,0 +1,382 @@
+// local referee for bots, which talk codingame protocol on stdin and stdout
+// Each bot runs as child process. Referee sends exact codingame input of each turn including
+// valid actions and enforces time limits of codingame. A time out, an illegal move, invalid output
+// or a terminated bot lose the match.
+
+use super::{
+    expected_valid_actions, format_action, parse_action, ConfigRecord, GameRecord, TurnInput,
+    UltTTT, UltTTTMCTSGame, UltTTTMove,
+};
+use anyhow::Context;
+use my_lib::my_mcts::{GameCache, MCTSGame, NoGameCache};
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// executable of a bot with arguments
+#[derive(Debug, Clone, PartialEq)]
+pub struct BotCommand {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl BotCommand {
+    // splits command line at whitespace: "path/to/bot --arg"
+    pub fn parse(command_line: &str) -> Option<BotCommand> {
+        let mut parts = command_line.split_whitespace().map(|part| part.to_string());
+        Some(BotCommand {
+            program: parts.next()?,
+            args: parts.collect(),
+        })
+    }
+}
+
+impl Display for BotCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in self.args.iter() {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViolationKind {
+    TimeOut(Duration),
+    // legal format, but no valid action
+    IllegalMove(UltTTTMove),
+    // output line is no move
+    InvalidOutput(String),
+    // bot closed its output or could not read its input
+    Disconnected,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub player: TicTacToeStatus,
+    pub kind: ViolationKind,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} ", self.player)?;
+        match &self.kind {
+            ViolationKind::TimeOut(elapsed) => write!(f, "timed out after {:?}", elapsed),
+            ViolationKind::IllegalMove(mv) => {
+                write!(f, "played illegal move {}", format_action(*mv))
+            }
+            ViolationKind::InvalidOutput(line) => write!(f, "sent invalid output {:?}", line),
+            ViolationKind::Disconnected => write!(f, "disconnected"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArenaResult {
+    // result of record is given from perspective of First; violating player loses
+    pub record: GameRecord,
+    pub violation: Option<Violation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arena {
+    // time limits of codingame
+    pub first_turn_limit: Duration,
+    pub turn_limit: Duration,
+    // forward stderr of bots, e.g. for debugging
+    pub show_stderr: bool,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Arena {
+            first_turn_limit: Duration::from_millis(1_000),
+            turn_limit: Duration::from_millis(100),
+            show_stderr: false,
+        }
+    }
+}
+
+impl Arena {
+    // plays one match: first bot plays as First, second bot as Second; error only, if a bot
+    // cannot be started
+    pub fn play(
+        &self,
+        first: &BotCommand,
+        second: &BotCommand,
+        first_starts: bool,
+    ) -> anyhow::Result<ArenaResult> {
+        let start_player = if first_starts {
+            TicTacToeStatus::First
+        } else {
+            TicTacToeStatus::Second
+        };
+        let mut record = GameRecord::new(start_player, ConfigRecord::new(), ConfigRecord::new());
+        let mut bots = [
+            BotProcess::spawn(first, self.show_stderr)?,
+            BotProcess::spawn(second, self.show_stderr)?,
+        ];
+        let mut state = record.initial_state();
+        let mut first_turn = [true; 2];
+        let mut last_move: Option<UltTTTMove> = None;
+        while state.get_status() == TicTacToeStatus::Vacant {
+            let player = state.current_player;
+            let index = if player == TicTacToeStatus::First {
+                0
+            } else {
+                1
+            };
+            let time_limit = if first_turn[index] {
+                self.first_turn_limit
+            } else {
+                self.turn_limit
+            };
+            first_turn[index] = false;
+            match bots[index].play_turn(&state, last_move, time_limit) {
+                Ok((mv, elapsed)) => {
+                    record.push_move(mv, elapsed, 0);
+                    state = state.try_apply(&mv).expect("valid action is legal");
+                    last_move = Some(mv);
+                }
+                Err(kind) => {
+                    // violating player loses
+                    record.result = Some(if player == TicTacToeStatus::First {
+                        0.0
+                    } else {
+                        1.0
+                    });
+                    return Ok(ArenaResult {
+                        record,
+                        violation: Some(Violation { player, kind }),
+                    });
+                }
+            }
+        }
+        // full meta board without winner is decided by number of won mini boards
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        record.result = UltTTTMCTSGame::evaluate(&state, &mut game_cache).map(|score| score as f64);
+        Ok(ArenaResult {
+            record,
+            violation: None,
+        })
+    }
+}
+
+struct BotProcess {
+    child: Child,
+    stdin: ChildStdin,
+    // lines of stdout of bot, read by a parallel thread
+    lines: mpsc::Receiver<String>,
+}
+
+impl BotProcess {
+    fn spawn(command: &BotCommand, show_stderr: bool) -> anyhow::Result<BotProcess> {
+        let mut child = Command::new(&command.program)
+            .args(&command.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(if show_stderr {
+                Stdio::inherit()
+            } else {
+                Stdio::null()
+            })
+            .spawn()
+            .with_context(|| format!("Failed to start bot {}", command))?;
+        let stdin = child.stdin.take().context("stdin of bot is piped")?;
+        let stdout = child.stdout.take().context("stdout of bot is piped")?;
+        let (tx, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(BotProcess {
+            child,
+            stdin,
+            lines,
+        })
+    }
+    // sends input of turn and waits for move of bot
+    fn play_turn(
+        &mut self,
+        state: &UltTTT,
+        opponent_move: Option<UltTTTMove>,
+        time_limit: Duration,
+    ) -> Result<(UltTTTMove, Duration), ViolationKind> {
+        let turn_input = TurnInput {
+            opponent_move,
+            valid_actions: expected_valid_actions(state),
+        };
+        self.stdin
+            .write_all(turn_input.to_string().as_bytes())
+            .and_then(|_| self.stdin.flush())
+            .map_err(|_| ViolationKind::Disconnected)?;
+        let start = Instant::now();
+        let line = match self.lines.recv_timeout(time_limit) {
+            Ok(line) => line,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                return Err(ViolationKind::TimeOut(start.elapsed()))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Err(ViolationKind::Disconnected),
+        };
+        let elapsed = start.elapsed();
+        match parse_action(&line) {
+            Ok(Some(mv)) if turn_input.valid_actions.contains(&mv) => Ok((mv, elapsed)),
+            Ok(Some(mv)) => Err(ViolationKind::IllegalMove(mv)),
+            _ => Err(ViolationKind::InvalidOutput(line)),
+        }
+    }
+}
+
+impl Drop for BotProcess {
+    fn drop(&mut self) {
+        // bots wait for input after end of game
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::SliceRandom;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // shell bot, which reads turn input and answers with output of answer
+    fn shell_bot(answer: &str) -> BotCommand {
+        let script = format!(
+            "while read opponent; do read count; read first; \
+             i=1; while [ $i -lt $count ]; do read action; i=$((i+1)); done; {}; done",
+            answer
+        );
+        BotCommand {
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), script],
+        }
+    }
+
+    fn first_valid_action_bot() -> BotCommand {
+        shell_bot("echo \"$first\"")
+    }
+
+    // plays given moves in order
+    fn scripted_bot(moves: &[UltTTTMove]) -> BotCommand {
+        let mut bot = shell_bot("echo \"$1 $2\"; shift 2");
+        bot.args.push("sh".to_string());
+        for mv in moves {
+            bot.args
+                .extend(format_action(*mv).split(' ').map(|value| value.to_string()));
+        }
+        bot
+    }
+
+    fn arena() -> Arena {
+        Arena {
+            first_turn_limit: Duration::from_millis(1_000),
+            turn_limit: Duration::from_millis(500),
+            show_stderr: false,
+        }
+    }
+
+    #[test]
+    fn test_full_match_of_legal_bots() {
+        for first_starts in [true, false] {
+            let result = arena()
+                .play(
+                    &first_valid_action_bot(),
+                    &first_valid_action_bot(),
+                    first_starts,
+                )
+                .unwrap();
+            assert_eq!(result.violation, None);
+            assert!(result.record.result.is_some());
+            // record replays to a finished game
+            let final_state = result.record.replay().last().unwrap().unwrap();
+            assert_ne!(final_state.get_status(), TicTacToeStatus::Vacant);
+            // first move of game is first cell of board, which search would never play
+            assert_eq!(
+                result.record.moves[0].to_move().unwrap(),
+                UltTTTMove::try_from((0, 0)).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_full_board_is_decided_by_won_boards() {
+        // random game, which fills meta board without winner and with more won boards of
+        // one player
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        let (state, moves) = loop {
+            let mut state = UltTTT::new();
+            let mut moves = Vec::new();
+            while state.get_status() == TicTacToeStatus::Vacant {
+                let mv = *state.legal_moves().as_slice().choose(&mut rng).unwrap();
+                state = state.try_apply(&mv).unwrap();
+                moves.push(mv);
+            }
+            if state.get_status() == TicTacToeStatus::Tie
+                && UltTTTMCTSGame::evaluate(&state, &mut game_cache) != Some(0.5)
+            {
+                break (state, moves);
+            }
+        };
+        let first_moves: Vec<UltTTTMove> = moves.iter().step_by(2).copied().collect();
+        let second_moves: Vec<UltTTTMove> = moves.iter().skip(1).step_by(2).copied().collect();
+        let result = arena()
+            .play(
+                &scripted_bot(&first_moves),
+                &scripted_bot(&second_moves),
+                true,
+            )
+            .unwrap();
+        assert_eq!(result.violation, None);
+        assert_eq!(result.record.moves.len(), moves.len());
+        assert_eq!(
+            result.record.result,
+            UltTTTMCTSGame::evaluate(&state, &mut game_cache).map(|score| score as f64)
+        );
+    }
+
+    #[test]
+    fn test_violations_lose_match() {
+        let arena = arena();
+        let legal = first_valid_action_bot();
+        // occupied cell in second turn of first
+        let result = arena.play(&shell_bot("echo 0 0"), &legal, true).unwrap();
+        let violation = result.violation.unwrap();
+        assert_eq!(violation.player, TicTacToeStatus::First);
+        assert!(matches!(violation.kind, ViolationKind::IllegalMove(_)));
+        assert_eq!(result.record.result, Some(0.0));
+
+        let result = arena.play(&legal, &shell_bot("echo hello"), true).unwrap();
+        assert_eq!(
+            result.violation.unwrap().kind,
+            ViolationKind::InvalidOutput("hello".to_string())
+        );
+        assert_eq!(result.record.result, Some(1.0));
+
+        let result = arena.play(&legal, &shell_bot("sleep 2"), false).unwrap();
+        let violation = result.violation.unwrap();
+        assert_eq!(violation.player, TicTacToeStatus::Second);
+        assert!(matches!(violation.kind, ViolationKind::TimeOut(_)));
+
+        let result = arena.play(&shell_bot("exit 0"), &legal, true).unwrap();
+        assert_eq!(result.violation.unwrap().kind, ViolationKind::Disconnected);
+
+        let missing = BotCommand::parse("./no_such_bot").unwrap();
+        assert!(arena.play(&missing, &legal, true).is_err());
+    }
+}
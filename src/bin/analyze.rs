@@ -0,0 +1,102 @@
+// analysis tool: searches a position and reports top moves with principal variation
+// usage: analyze [--json] [--top <k>] [--time <ms> | --iterations <n>] [--moves <moves>] [notation]
+// position is given by notation or by moves from initial position in codingame format
+// "row col,row col,...". Without position the initial position is analyzed.
+
+use anyhow::{bail, Context};
+use cg_ultimate_tic_tac_toe::{
+    analyze_position, parse_action, AnalysisBudget, DecidedOutcomeCutoff, HPWDefaultTTTNoGameCache,
+    UltTTT, UltTTTHeuristic, UltTTTHeuristicConfig, UltTTTMCTSConfig, UltTTTMCTSGame,
+    UltTTTZobristTT,
+};
+use my_lib::my_mcts::{CachedUTC, DynamicC, MCTSGame, PlainMCTS};
+use std::time::Duration;
+
+type UltTTTMCTS = PlainMCTS<
+    UltTTTMCTSGame,
+    UltTTTHeuristic,
+    UltTTTMCTSConfig,
+    CachedUTC,
+    UltTTTZobristTT,
+    DynamicC,
+    HPWDefaultTTTNoGameCache,
+    DecidedOutcomeCutoff,
+>;
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error occurred: {:?}", err);
+    }
+}
+
+fn run() -> anyhow::Result<()> {
+    let mut json = false;
+    let mut top_k = 5;
+    let mut budget = AnalysisBudget::Time(Duration::from_millis(1_000));
+    let mut moves: Option<String> = None;
+    let mut notation: Vec<String> = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .with_context(|| format!("{} needs a value", name))
+        };
+        match arg.as_str() {
+            "--json" => json = true,
+            "--top" => top_k = value("--top")?.parse().context("--top needs a number")?,
+            "--time" => {
+                let millis = value("--time")?
+                    .parse()
+                    .context("--time needs milliseconds")?;
+                budget = AnalysisBudget::Time(Duration::from_millis(millis));
+            }
+            "--iterations" => {
+                let iterations = value("--iterations")?
+                    .parse()
+                    .context("--iterations needs a number")?;
+                budget = AnalysisBudget::Iterations(iterations);
+            }
+            "--moves" => moves = Some(value("--moves")?),
+            _ if arg.starts_with("--") => bail!("unknown option {}", arg),
+            _ => notation.push(arg),
+        }
+    }
+    let state = match (moves, notation.is_empty()) {
+        (Some(_), false) => bail!("position is given by notation or by moves, not by both"),
+        (Some(moves), true) => state_from_moves(&moves)?,
+        (None, false) => UltTTT::from_notation(&notation.join(" "))?,
+        (None, true) => UltTTT::new(),
+    };
+
+    // same search as bot
+    let expected_num_nodes = 2_000_000;
+    let mut mcts_config = UltTTTMCTSConfig::optimized_v05_initial_phase();
+    mcts_config.optimized_v05_set_exploration_boost(UltTTTMCTSGame::current_player(&state));
+    let mut mcts = UltTTTMCTS::new(
+        mcts_config,
+        UltTTTHeuristicConfig::optimized_v05(),
+        expected_num_nodes,
+    );
+    let analysis = analyze_position(&mut mcts, &state, budget, top_k);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&analysis)?);
+    } else {
+        println!("{}", state);
+        println!("{}", analysis);
+    }
+    Ok(())
+}
+
+fn state_from_moves(moves: &str) -> anyhow::Result<UltTTT> {
+    moves
+        .split(',')
+        .filter(|action| !action.trim().is_empty())
+        .enumerate()
+        .try_fold(UltTTT::new(), |state, (ply, action)| {
+            let mv = parse_action(action)?
+                .with_context(|| format!("\"{}\" is no move", action.trim()))?;
+            state
+                .try_apply(&mv)
+                .with_context(|| format!("illegal move {} at ply {}", action.trim(), ply + 1))
+        })
+}
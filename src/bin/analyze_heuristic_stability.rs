@@ -3,15 +3,22 @@
 use rayon::prelude::*;
 use statrs::statistics::Statistics;
 
-use cg_ultimate_tic_tac_toe::utilities::*;
+use cg_ultimate_tic_tac_toe::{utilities::*, NoGameRecordSink, Opponent};
 
 /// Run multiple matches
-fn run_matches(config: Config, total_matches: usize) -> Vec<f64> {
+fn run_matches(config: Config, total_matches: usize) -> Vec<MatchReport> {
     (0..total_matches)
         .into_par_iter()
         .map(|i| {
             let is_starting_player = i % 2 == 0;
-            run_match(config.clone(), is_starting_player).0
+            run_match(
+                config.clone(),
+                &Opponent::default(),
+                is_starting_player,
+                &MatchBudget::default(),
+                rand::random(),
+                NoGameRecordSink {},
+            )
         })
         .collect()
 }
@@ -21,7 +28,9 @@ fn analyze_stability(config: Config) {
     let eval_sizes = [10, 25, 50, 100];
 
     for &size in &eval_sizes {
-        let scores = run_matches(config.clone(), size);
+        let reports = run_matches(config.clone(), size);
+        let summary: MatchSummary = reports.iter().collect();
+        let scores: Vec<f64> = reports.iter().map(|report| report.score()).collect();
         let mean = scores.clone().mean();
         let std_dev = scores.std_dev();
 
@@ -29,6 +38,7 @@ fn analyze_stability(config: Config) {
             "After {:>3} matches: Average Score = {:.3}, Std Dev = {:.3}",
             size, mean, std_dev
         );
+        println!("{}\n", summary);
     }
 }
 
@@ -1,6 +1,6 @@
 // util to analyze mutation events in the log files
 
-use cg_ultimate_tic_tac_toe::{config::*, utilities::*};
+use cg_ultimate_tic_tac_toe::{config::*, utilities::*, OpponentPool};
 use chrono::NaiveDate;
 use my_lib::my_optimizer::{
     analyze_evo_log_entries, read_logs_from_dir, DefaultLogEntry, EvoFields, EvoSpan,
@@ -70,6 +70,11 @@ fn run() -> anyhow::Result<()> {
         num_matches: 100,
         early_break_off: None,
         progress_step_size: 10,
+        budget: MatchBudget::default(),
+        seed: None,
+        opponents: OpponentPool::default(),
+        match_threads: available_cores(),
+        sprt: None,
         estimated_num_of_steps: 100,
     };
 
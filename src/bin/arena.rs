@@ -0,0 +1,102 @@
+// arena: plays matches between two bot executables with codingame protocol and time limits
+// usage: arena <bot_a> <bot_b> [--games <n>] [--records <file.jsonl>] [--first-turn-ms <ms>]
+//              [--turn-ms <ms>] [--stderr]
+// Bots are given as command lines, e.g. "target/release/fusion_of_cg_ultimate_tic_tac_toe".
+// Start player alternates; bot_a always plays as First of records.
+
+use anyhow::{bail, Context};
+use cg_ultimate_tic_tac_toe::{Arena, BotCommand, GameRecordSink, JsonLinesGameRecordWriter};
+use std::time::Duration;
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error occurred: {:?}", err);
+    }
+}
+
+fn run() -> anyhow::Result<()> {
+    let usage = "usage: arena <bot_a> <bot_b> [--games <n>] [--records <file.jsonl>] \
+                 [--first-turn-ms <ms>] [--turn-ms <ms>] [--stderr]";
+    let mut arena = Arena::default();
+    let mut games = 2;
+    let mut records: Option<JsonLinesGameRecordWriter> = None;
+    let mut bots: Vec<BotCommand> = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .with_context(|| format!("{} needs a value", name))
+        };
+        match arg.as_str() {
+            "--games" => {
+                games = value("--games")?
+                    .parse()
+                    .context("--games needs a number")?
+            }
+            "--records" => records = Some(JsonLinesGameRecordWriter::create(value("--records")?)?),
+            "--first-turn-ms" => {
+                let millis = value("--first-turn-ms")?
+                    .parse()
+                    .context("--first-turn-ms needs milliseconds")?;
+                arena.first_turn_limit = Duration::from_millis(millis);
+            }
+            "--turn-ms" => {
+                let millis = value("--turn-ms")?
+                    .parse()
+                    .context("--turn-ms needs milliseconds")?;
+                arena.turn_limit = Duration::from_millis(millis);
+            }
+            "--stderr" => arena.show_stderr = true,
+            _ if arg.starts_with("--") => bail!("unknown option {}\n{}", arg, usage),
+            _ => bots.push(BotCommand::parse(&arg).context(usage)?),
+        }
+    }
+    let [bot_a, bot_b] = bots.as_slice() else {
+        bail!(usage);
+    };
+
+    // score from perspective of bot_a
+    let mut score = 0.0;
+    let (mut wins, mut draws, mut losses, mut violations) = (0, 0, 0, 0);
+    for game in 0..games {
+        let a_starts = game % 2 == 0;
+        let result = arena.play(bot_a, bot_b, a_starts)?;
+        let game_score = result.record.result.unwrap_or(0.5);
+        score += game_score;
+        match game_score {
+            s if s > 0.5 => wins += 1,
+            s if s < 0.5 => losses += 1,
+            _ => draws += 1,
+        }
+        let violation = match result.violation.as_ref() {
+            Some(violation) => {
+                violations += 1;
+                format!(" ({})", violation)
+            }
+            None => String::new(),
+        };
+        println!(
+            "game {}: bot_a {}, {} plies, score {:.1}{}",
+            game + 1,
+            if a_starts { "starts" } else { "second" },
+            result.record.moves.len(),
+            game_score,
+            violation
+        );
+        if let Some(records) = records.as_mut() {
+            records.insert(result.record)?;
+        }
+    }
+    println!();
+    println!("bot_a: {}", bot_a);
+    println!("bot_b: {}", bot_b);
+    println!(
+        "bot_a wins {}, draws {}, losses {}, score {:.3}",
+        wins,
+        draws,
+        losses,
+        score / games.max(1) as f64
+    );
+    println!("violations: {}", violations);
+    Ok(())
+}
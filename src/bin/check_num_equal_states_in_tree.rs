@@ -1,7 +1,7 @@
 // MCTS may benefit from state caching (transposition table) to avoid recalculating the same state multiple times.
 // With this tool we analyze the final game tree of a match of UltTTT for the number of equal states, which could have been cached.
 
-use cg_ultimate_tic_tac_toe::{config::*, utilities::*};
+use cg_ultimate_tic_tac_toe::{config::*, utilities::*, NoGameRecordSink, Opponent};
 use my_lib::my_mcts::{MCTSNode, MCTSTree};
 use std::collections::{HashMap, HashSet};
 
@@ -18,11 +18,24 @@ fn run() -> anyhow::Result<()> {
     };
 
     println!("Running match...");
-    let (_, first, second) = run_match(config, true);
+    let (_, trees) = run_match_with_trees(
+        config,
+        &Opponent::default(),
+        true,
+        &MatchBudget::default(),
+        rand::random(),
+        NoGameRecordSink {},
+    );
+    let first = trees.heuristic;
+    let second = trees
+        .opponent
+        .into_any()
+        .downcast::<UltTTTMCTSFirst>()
+        .expect("engine of heuristic opponent is UltTTTMCTSFirst");
 
     println!("Collecting nodes of same tree level of first...");
     let mut nodes_of_same_tree_level: HashMap<usize, Vec<usize>> = HashMap::new();
-    collect_nodes_of_same_tree_level_first(&first, 0, 0, &mut nodes_of_same_tree_level);
+    collect_nodes_of_same_tree_level(&first, 0, 0, &mut nodes_of_same_tree_level);
 
     println!("Comparing node states of same tree level...");
     let mut cross_check = 0;
@@ -62,7 +75,7 @@ fn run() -> anyhow::Result<()> {
 
     println!("Collecting nodes of same tree level of second...");
     nodes_of_same_tree_level.clear();
-    collect_nodes_of_same_tree_level_second(&second, 0, 0, &mut nodes_of_same_tree_level);
+    collect_nodes_of_same_tree_level(&second, 0, 0, &mut nodes_of_same_tree_level);
 
     println!("Comparing node states of same tree level...");
     let mut number_of_duplicates_of_each_level: HashMap<usize, usize> = HashMap::new();
@@ -96,7 +109,7 @@ fn run() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn collect_nodes_of_same_tree_level_first(
+fn collect_nodes_of_same_tree_level(
     mcts: &UltTTTMCTSFirst,
     index: usize,
     level: usize,
@@ -108,22 +121,6 @@ fn collect_nodes_of_same_tree_level_first(
         nodes_of_same_tree_level.insert(level, vec![index]);
     }
     for (child, _) in mcts.tree.get_children(index) {
-        collect_nodes_of_same_tree_level_first(mcts, *child, level + 1, nodes_of_same_tree_level);
-    }
-}
-
-fn collect_nodes_of_same_tree_level_second(
-    mcts: &UltTTTMCTSSecond,
-    index: usize,
-    level: usize,
-    nodes_of_same_tree_level: &mut HashMap<usize, Vec<usize>>,
-) {
-    if let Some(states) = nodes_of_same_tree_level.get_mut(&level) {
-        states.push(index);
-    } else {
-        nodes_of_same_tree_level.insert(level, vec![index]);
-    }
-    for (child, _) in mcts.tree.get_children(index) {
-        collect_nodes_of_same_tree_level_second(mcts, *child, level + 1, nodes_of_same_tree_level);
+        collect_nodes_of_same_tree_level(mcts, *child, level + 1, nodes_of_same_tree_level);
     }
 }
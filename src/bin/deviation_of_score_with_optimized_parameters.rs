@@ -1,6 +1,6 @@
 // analyzing deviation of score with optimizer parameter values
 
-use cg_ultimate_tic_tac_toe::utilities::*;
+use cg_ultimate_tic_tac_toe::{utilities::*, OpponentPool};
 use my_lib::my_optimizer::*;
 use statrs::statistics::Statistics;
 use std::fs::File;
@@ -45,6 +45,11 @@ fn run() -> anyhow::Result<()> {
         num_matches: 100,
         early_break_off: None,
         progress_step_size: 10,
+        budget: MatchBudget::default(),
+        seed: None,
+        opponents: OpponentPool::default(),
+        match_threads: available_cores(),
+        sprt: None,
         estimated_num_of_steps: num_test_runs * merged_population.size() * 100, // 100 matches per candidate
     };
 
@@ -1,6 +1,6 @@
 // search for optimal parameters with evolutionary optimizer
 
-use cg_ultimate_tic_tac_toe::utilities::*;
+use cg_ultimate_tic_tac_toe::{utilities::*, OpponentPool};
 use my_lib::my_optimizer::*;
 use tracing::{info, span, Level};
 
@@ -53,6 +53,11 @@ fn run() -> anyhow::Result<()> {
             score_threshold: 0.5,
         }),
         progress_step_size: 10,
+        budget: MatchBudget::default(),
+        seed: None,
+        opponents: OpponentPool::default(),
+        match_threads: 1,
+        sprt: None,
         estimated_num_of_steps: 50 * 100, // 50 candidates and 100 matches
     };
 
@@ -110,6 +115,11 @@ fn run() -> anyhow::Result<()> {
             score_threshold: 0.55,
         }),
         progress_step_size: 10,
+        budget: MatchBudget::default(),
+        seed: None,
+        opponents: OpponentPool::default(),
+        match_threads: 1,
+        sprt: None,
         estimated_num_of_steps: evolutionary_optimizer_configuration
             .get_estimate_of_cycles(&param_bounds)?
             * 100, // 100 matches
@@ -1,6 +1,6 @@
 // coarse grid search to optimize parameters of UltTTT
 
-use cg_ultimate_tic_tac_toe::utilities::*;
+use cg_ultimate_tic_tac_toe::{utilities::*, OpponentPool};
 use my_lib::my_optimizer::*;
 use tracing::{info, span, Level};
 
@@ -69,6 +69,11 @@ fn run() -> anyhow::Result<()> {
             score_threshold: 0.4,
         }),
         progress_step_size: 10,
+        budget: MatchBudget::default(),
+        seed: None,
+        opponents: OpponentPool::default(),
+        match_threads: 1,
+        sprt: None,
         estimated_num_of_steps: grid_configuration.get_estimate_of_cycles(&param_bounds)? * 100, // 100 matches per candidate
     };
 
@@ -1,6 +1,6 @@
 // just a small helper tool
 
-use cg_ultimate_tic_tac_toe::utilities::*;
+use cg_ultimate_tic_tac_toe::{utilities::*, OpponentPool};
 use my_lib::my_optimizer::*;
 use tracing::{info, span, Level};
 
@@ -38,6 +38,11 @@ fn run() -> anyhow::Result<()> {
         num_matches: 100,
         early_break_off: None,
         progress_step_size: 10,
+        budget: MatchBudget::default(),
+        seed: None,
+        opponents: OpponentPool::default(),
+        match_threads: available_cores(),
+        sprt: None,
         estimated_num_of_steps: 20 // 20 candidates
             * 100, // 100 matches
     };
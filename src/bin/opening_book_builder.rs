@@ -0,0 +1,88 @@
+// opening book builder: analyzes early positions with long MCTS searches and writes encoded book
+// usage: opening_book_builder <max_ply> <seconds_per_position> [output_file]
+// Encoded book is printed and written to output_file; embed it as EMBEDDED_OPENING_BOOK.
+
+use anyhow::Context;
+use cg_ultimate_tic_tac_toe::{
+    build_opening_book, format_action, DecidedOutcomeCutoff, HPWDefaultTTTNoGameCache, UltTTT,
+    UltTTTHeuristic, UltTTTHeuristicConfig, UltTTTMCTSConfig, UltTTTMCTSGame, UltTTTMove,
+    UltTTTZobristTT,
+};
+use my_lib::my_mcts::{CachedUTC, DynamicC, MCTSAlgo, MCTSGame, PlainMCTS};
+use std::time::{Duration, Instant};
+
+type UltTTTMCTS = PlainMCTS<
+    UltTTTMCTSGame,
+    UltTTTHeuristic,
+    UltTTTMCTSConfig,
+    CachedUTC,
+    UltTTTZobristTT,
+    DynamicC,
+    HPWDefaultTTTNoGameCache,
+    DecidedOutcomeCutoff,
+>;
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error occurred: {:?}", err);
+    }
+}
+
+fn run() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let usage = "usage: opening_book_builder <max_ply> <seconds_per_position> [output_file]";
+    let max_ply: usize = args
+        .first()
+        .context(usage)?
+        .parse()
+        .context("max_ply must be a positive number")?;
+    let seconds_per_position: f64 = args
+        .get(1)
+        .context(usage)?
+        .parse()
+        .context("seconds_per_position must be a number")?;
+    let time_per_position = Duration::from_secs_f64(seconds_per_position);
+
+    let start = Instant::now();
+    let mut position_counter = 0;
+    let book = build_opening_book(max_ply, |state| {
+        position_counter += 1;
+        let best_move = analyze(state, time_per_position);
+        println!(
+            "position {} ({:?}): {} -> {}",
+            position_counter,
+            start.elapsed(),
+            state.to_notation(),
+            format_action(best_move)
+        );
+        best_move
+    });
+    let encoded = book.encode();
+    println!();
+    println!("entries: {}", book.len());
+    println!("size: {} bytes", encoded.len());
+    println!("{}", encoded);
+    if let Some(output_file) = args.get(2) {
+        std::fs::write(output_file, &encoded)
+            .with_context(|| format!("failed to write {}", output_file))?;
+    }
+    Ok(())
+}
+
+// same search as bot, but with much more time
+fn analyze(state: &UltTTT, time_per_position: Duration) -> UltTTTMove {
+    let expected_num_nodes = 2_000_000;
+    let mut mcts_config = UltTTTMCTSConfig::optimized_v05_initial_phase();
+    mcts_config.optimized_v05_set_exploration_boost(UltTTTMCTSGame::current_player(state));
+    let mut mcts = UltTTTMCTS::new(
+        mcts_config,
+        UltTTTHeuristicConfig::optimized_v05(),
+        expected_num_nodes,
+    );
+    mcts.set_root(state);
+    let start = Instant::now();
+    while start.elapsed() < time_per_position {
+        mcts.iterate();
+    }
+    *mcts.select_move()
+}
@@ -0,0 +1,49 @@
+// perft tool: counts move sequences of given depth and prints count of each move (divide)
+// usage: perft <depth> [notation]
+// without notation the initial position is used
+
+use anyhow::Context;
+use cg_ultimate_tic_tac_toe::{format_action, perft_divide, UltTTT};
+use std::time::Instant;
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error occurred: {:?}", err);
+    }
+}
+
+fn run() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let depth: usize = args
+        .first()
+        .context("usage: perft <depth> [notation]")?
+        .parse()
+        .ok()
+        // perft of depth 0 is 1 without any move to divide by
+        .filter(|depth| *depth > 0)
+        .context("depth must be a positive number")?;
+    let state = match args.get(1..) {
+        Some(notation) if !notation.is_empty() => UltTTT::from_notation(&notation.join(" "))?,
+        _ => UltTTT::new(),
+    };
+    println!("position: {}", state.to_notation());
+    println!("{}", state);
+
+    let start = Instant::now();
+    let divide = perft_divide(&state, depth);
+    let elapsed = start.elapsed();
+    // moves are printed in codingame output format "row col"
+    for (mv, count) in divide.iter() {
+        println!("{}: {}", format_action(*mv), count);
+    }
+    let total: u64 = divide.iter().map(|(_, count)| count).sum();
+    println!();
+    println!("moves: {}", divide.len());
+    println!("total: {}", total);
+    println!(
+        "time: {:.3}s ({:.0} sequences/s)",
+        elapsed.as_secs_f64(),
+        total as f64 / elapsed.as_secs_f64().max(1e-9)
+    );
+    Ok(())
+}
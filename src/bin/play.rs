@@ -0,0 +1,309 @@
+// interactive game of a human against the engine in the terminal
+// usage: play [--human x|o] [--time <ms>] [--top <k>] [--no-color] [notation]
+// Moves are entered as codingame coordinates "row col" (0-8) or as "board:cell" with indices 0-8
+// of mini board and cell in reading order, e.g. "4:0" is top left cell of center mini board.
+// Commands: undo, hint, help, quit. X always starts; a notation sets start position.
+
+use anyhow::{bail, Context};
+use cg_ultimate_tic_tac_toe::{
+    analyze_position, expected_valid_actions, format_action, parse_action, AnalysisBudget,
+    DecidedOutcomeCutoff, HPWDefaultTTTNoGameCache, MoveError, PositionAnalysis, UltTTT,
+    UltTTTHeuristic, UltTTTHeuristicConfig, UltTTTMCTSConfig, UltTTTMCTSGame, UltTTTMove,
+    UltTTTZobristTT,
+};
+use my_lib::my_mcts::{CachedUTC, DynamicC, GameCache, MCTSAlgo, MCTSGame, NoGameCache, PlainMCTS};
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+use std::fmt::Write;
+use std::io::BufRead;
+use std::time::Duration;
+
+type UltTTTMCTS = PlainMCTS<
+    UltTTTMCTSGame,
+    UltTTTHeuristic,
+    UltTTTMCTSConfig,
+    CachedUTC,
+    UltTTTZobristTT,
+    DynamicC,
+    HPWDefaultTTTNoGameCache,
+    DecidedOutcomeCutoff,
+>;
+
+const HELP: &str = concat!(
+    "enter a move as \"row col\" (0-8) or as \"board:cell\" (0-8 in reading order)\n",
+    "commands: undo (take back your last move), hint (analysis for you), help, quit"
+);
+
+// ansi escape codes
+const RESET: &str = "\x1b[0m";
+const LAST_MOVE: &str = "\x1b[1;7m";
+const ALLOWED: &str = "\x1b[1;32m";
+const FIRST_BOARD: &str = "\x1b[31m";
+const SECOND_BOARD: &str = "\x1b[34m";
+const TIED_BOARD: &str = "\x1b[2m";
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error occurred: {:?}", err);
+    }
+}
+
+fn run() -> anyhow::Result<()> {
+    let mut human = TicTacToeStatus::First;
+    let mut budget = AnalysisBudget::Time(Duration::from_millis(1_000));
+    let mut top_k = 5;
+    let mut color = true;
+    let mut notation: Vec<String> = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .with_context(|| format!("{} needs a value", name))
+        };
+        match arg.as_str() {
+            "--human" => {
+                human = match value("--human")?.to_lowercase().as_str() {
+                    "x" => TicTacToeStatus::First,
+                    "o" => TicTacToeStatus::Second,
+                    side => bail!("--human needs x or o, got {}", side),
+                }
+            }
+            "--time" => {
+                let millis = value("--time")?
+                    .parse()
+                    .context("--time needs milliseconds")?;
+                budget = AnalysisBudget::Time(Duration::from_millis(millis));
+            }
+            "--top" => top_k = value("--top")?.parse().context("--top needs a number")?,
+            "--no-color" => color = false,
+            _ if arg.starts_with("--") => bail!("unknown option {}", arg),
+            _ => notation.push(arg),
+        }
+    }
+    let start = if notation.is_empty() {
+        UltTTT::new()
+    } else {
+        UltTTT::from_notation(&notation.join(" "))?
+    };
+
+    let engine_player = match human {
+        TicTacToeStatus::First => TicTacToeStatus::Second,
+        _ => TicTacToeStatus::First,
+    };
+    let mut engine = new_engine(engine_player);
+    // states of game and moves, which led to them
+    let mut history: Vec<(UltTTT, Option<UltTTTMove>)> = vec![(start, None)];
+    let mut lines = std::io::stdin().lock().lines();
+    println!("{}", HELP);
+    loop {
+        let (state, last_move) = *history.last().expect("history starts with start position");
+        println!();
+        print!("{}", render(&state, last_move, color));
+        if state.get_status() == TicTacToeStatus::Vacant
+            && UltTTTMCTSGame::current_player(&state) == engine_player
+        {
+            let (mv, analysis) = search(&mut engine, &state, budget, top_k);
+            match analysis.top_moves.first() {
+                Some(best) => println!(
+                    "engine plays {} with evaluation {:.3}",
+                    format_action(mv),
+                    best.mean_value
+                ),
+                None => println!("engine plays {}", format_action(mv)),
+            }
+            println!("{}", analysis);
+            let state = state.try_apply(&mv).context("engine move is legal")?;
+            history.push((state, Some(mv)));
+            continue;
+        }
+        match state.get_status() {
+            TicTacToeStatus::Vacant => println!(
+                "your move as {}, allowed mini boards: {}",
+                player_symbol(human),
+                allowed_boards(&state)
+            ),
+            _ => println!("game over: {}; undo or quit", result_text(&state, human)),
+        }
+        let Some(line) = lines.next() else {
+            return Ok(());
+        };
+        let input = line?.trim().to_lowercase();
+        match input.as_str() {
+            "" => (),
+            "quit" | "exit" | "q" => return Ok(()),
+            "help" | "?" => println!("{}", HELP),
+            "undo" | "u" => {
+                // back to previous position with human to move
+                let previous = history[..history.len() - 1].iter().rposition(|(state, _)| {
+                    state.get_status() == TicTacToeStatus::Vacant
+                        && UltTTTMCTSGame::current_player(state) == human
+                });
+                match previous {
+                    Some(index) => history.truncate(index + 1),
+                    None => println!("nothing to undo"),
+                }
+            }
+            "hint" | "h" if state.get_status() == TicTacToeStatus::Vacant => {
+                let (_, analysis) = search(&mut new_engine(human), &state, budget, top_k);
+                println!("{}", analysis);
+            }
+            _ if state.get_status() != TicTacToeStatus::Vacant => {
+                println!("game is over; undo or quit")
+            }
+            _ => match parse_move(&input) {
+                Some(mv) => match state.try_apply(&mv) {
+                    Ok(new_state) => history.push((new_state, Some(mv))),
+                    Err(MoveError::OccupiedCell(_)) => println!("cell is already occupied"),
+                    Err(MoveError::WrongMiniBoard { .. } | MoveError::BoardAlreadyDecided(_)) => {
+                        println!(
+                            "move must be played in mini boards {}",
+                            allowed_boards(&state)
+                        )
+                    }
+                    Err(err) => println!("illegal move: {}", err),
+                },
+                None => println!("unknown input {:?}\n{}", input, HELP),
+            },
+        }
+    }
+}
+
+// same search as bot
+fn new_engine(player: TicTacToeStatus) -> UltTTTMCTS {
+    let expected_num_nodes = 2_000_000;
+    let mut mcts_config = UltTTTMCTSConfig::optimized_v05_initial_phase();
+    mcts_config.optimized_v05_set_exploration_boost(player);
+    UltTTTMCTS::new(
+        mcts_config,
+        UltTTTHeuristicConfig::optimized_v05(),
+        expected_num_nodes,
+    )
+}
+
+fn search(
+    engine: &mut UltTTTMCTS,
+    state: &UltTTT,
+    budget: AnalysisBudget,
+    top_k: usize,
+) -> (UltTTTMove, PositionAnalysis) {
+    let analysis = analyze_position(engine, state, budget, top_k);
+    (*engine.select_move(), analysis)
+}
+
+// "row col" or "board:cell"
+fn parse_move(input: &str) -> Option<UltTTTMove> {
+    if let Some((board, cell)) = input.split_once(':') {
+        let board: u8 = board.trim().parse().ok()?;
+        let cell: u8 = cell.trim().parse().ok()?;
+        if board > 8 || cell > 8 {
+            return None;
+        }
+        let x = 3 * (board % 3) + cell % 3;
+        let y = 3 * (board / 3) + cell / 3;
+        return UltTTTMove::try_from((x, y)).ok();
+    }
+    parse_action(input).ok().flatten()
+}
+
+// indices of mini boards in reading order, which contain valid actions
+fn allowed_boards(state: &UltTTT) -> String {
+    let mut boards: Vec<u8> = expected_valid_actions(state)
+        .into_iter()
+        .map(|mv| {
+            let (x, y) = <(u8, u8)>::from(mv);
+            3 * (y / 3) + x / 3
+        })
+        .collect();
+    boards.sort();
+    boards.dedup();
+    if boards.len() == 9 {
+        return "all".to_string();
+    }
+    boards
+        .iter()
+        .map(|board| board.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// board with codingame coordinates; vacant cells of allowed mini boards are marked with "·"
+fn render(state: &UltTTT, last_move: Option<UltTTTMove>, color: bool) -> String {
+    let valid_actions = if state.get_status() == TicTacToeStatus::Vacant {
+        expected_valid_actions(state)
+    } else {
+        Vec::new()
+    };
+    let status_map = state.get_status_map();
+    let mut board = String::new();
+    let _ = writeln!(board, "    0 1 2   3 4 5   6 7 8");
+    for y in 0..9 {
+        let line = match y {
+            0 => "┌───────┬───────┬───────┐",
+            _ if y % 3 == 0 => "├───────┼───────┼───────┤",
+            _ => "",
+        };
+        if !line.is_empty() {
+            let _ = writeln!(board, "  {}", line);
+        }
+        let _ = write!(board, "{} ", y);
+        for x in 0..9 {
+            if x % 3 == 0 {
+                board.push_str("│ ");
+            }
+            let mv = UltTTTMove::try_from((x, y)).expect("coordinates are on board");
+            let allowed = valid_actions.contains(&mv);
+            let symbol = match state
+                .get_mini_board(mv.status_index)
+                .get_cell_value(mv.mini_board_index)
+            {
+                TicTacToeStatus::Vacant if allowed => '·',
+                TicTacToeStatus::Vacant => ' ',
+                player => player_symbol(player),
+            };
+            let style = if last_move == Some(mv) {
+                LAST_MOVE
+            } else if allowed {
+                ALLOWED
+            } else {
+                match status_map.get_cell_value(mv.status_index) {
+                    TicTacToeStatus::First => FIRST_BOARD,
+                    TicTacToeStatus::Second => SECOND_BOARD,
+                    TicTacToeStatus::Tie => TIED_BOARD,
+                    TicTacToeStatus::Vacant => "",
+                }
+            };
+            if color && !style.is_empty() {
+                let _ = write!(board, "{}{}{} ", style, symbol, RESET);
+            } else {
+                let _ = write!(board, "{} ", symbol);
+            }
+        }
+        board.push_str("│\n");
+    }
+    let _ = writeln!(board, "  └───────┴───────┴───────┘");
+    board
+}
+
+fn player_symbol(player: TicTacToeStatus) -> char {
+    match player {
+        TicTacToeStatus::First => 'X',
+        TicTacToeStatus::Second => 'O',
+        _ => unreachable!("Player is always First or Second"),
+    }
+}
+
+// full meta board without winner is decided by tie break of rules (see UltTTTMCTSGame::evaluate())
+fn result_text(state: &UltTTT, human: TicTacToeStatus) -> &'static str {
+    let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+    let score = UltTTTMCTSGame::evaluate(state, &mut game_cache).expect("game is over");
+    // score is from perspective of First
+    let human_score = if human == TicTacToeStatus::First {
+        score
+    } else {
+        1.0 - score
+    };
+    match human_score {
+        0.5 => "draw",
+        1.0 => "you win",
+        _ => "engine wins",
+    }
+}
@@ -1,6 +1,6 @@
 // random search of optimal parameters
 
-use cg_ultimate_tic_tac_toe::utilities::*;
+use cg_ultimate_tic_tac_toe::{utilities::*, OpponentPool};
 use my_lib::my_optimizer::*;
 use tracing::{info, span, Level};
 
@@ -55,6 +55,11 @@ fn run() -> anyhow::Result<()> {
             score_threshold: 0.5,
         }),
         progress_step_size: 10,
+        budget: MatchBudget::default(),
+        seed: None,
+        opponents: OpponentPool::default(),
+        match_threads: 1,
+        sprt: None,
         estimated_num_of_steps: random_search_configuration
             .get_estimate_of_cycles(&param_bounds)?
             * 100, // 100 matches
@@ -3,14 +3,21 @@
 use rayon::prelude::*;
 use statrs::statistics::Statistics;
 
-use cg_ultimate_tic_tac_toe::utilities::*;
+use cg_ultimate_tic_tac_toe::{utilities::*, NoGameRecordSink, Opponent};
 
-fn run_matches(config: Config, total_matches: usize) -> Vec<f64> {
+fn run_matches(config: Config, total_matches: usize) -> Vec<MatchReport> {
     (0..total_matches)
         .into_par_iter()
         .map(|i| {
             let is_starting_player = i % 2 == 0;
-            run_match(config.clone(), is_starting_player).0
+            run_match(
+                config.clone(),
+                &Opponent::default(),
+                is_starting_player,
+                &MatchBudget::default(),
+                rand::random(),
+                NoGameRecordSink {},
+            )
         })
         .collect()
 }
@@ -39,13 +46,21 @@ fn sensitivity_analysis(
 
         let test_params = (&config_vec[..]).try_into()?;
 
-        let scores = run_matches(test_params, matches_per_step);
+        let reports = run_matches(test_params, matches_per_step);
+        let summary: MatchSummary = reports.iter().collect();
+        let scores: Vec<f64> = reports.iter().map(|report| report.score()).collect();
         let mean = scores.clone().mean();
         let std_dev = scores.std_dev();
 
         println!(
-            "value: {:>6.3} | mean score: {:.3} | std dev: {:.3}",
-            param_value, mean, std_dev
+            "value: {:>6.3} | mean score: {:.3} | std dev: {:.3} | W-D-L: {}-{}-{} | iterations/turn: {:.0}",
+            param_value,
+            mean,
+            std_dev,
+            summary.results.wins,
+            summary.results.draws,
+            summary.results.losses,
+            summary.heuristic.iterations_per_turn()
         );
     }
     println!();
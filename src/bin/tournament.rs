@@ -0,0 +1,164 @@
+// tournament of engines with Bradley-Terry ratings and crosstable
+// usage: tournament [players] [--gauntlet <name>] [--games <n>] [--first-turn-ms <ms>]
+//                   [--turn-ms <ms>] [--threads <n>] [--records <file.jsonl>] [--output <file>]
+// A player is a preset (see TournamentPlayer::PRESETS) or "name=config.json" with a Config of
+// UltTTT as JSON, which plays with its exploration boost like a candidate of optimization. Without
+// players all presets take part. --games is number of games per pairing.
+
+use anyhow::{bail, Context};
+use cg_ultimate_tic_tac_toe::{
+    utilities::Config, GameRecordSink, JsonLinesGameRecordWriter, NoGameRecordSink, Opponent,
+    Pairing, TimeManager, Tournament, TournamentPlayer, TournamentResult,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error occurred: {:?}", err);
+    }
+}
+
+fn run() -> anyhow::Result<()> {
+    let usage = "usage: tournament [players] [--gauntlet <name>] [--games <n>] \
+                 [--first-turn-ms <ms>] [--turn-ms <ms>] [--threads <n>] \
+                 [--records <file.jsonl>] [--output <file>]";
+    let mut players: Vec<TournamentPlayer> = Vec::new();
+    let mut gauntlet: Option<String> = None;
+    let mut games_per_pairing = 10;
+    let mut time_manager = TimeManager::default();
+    let mut threads: Option<usize> = None;
+    let mut records: Option<JsonLinesGameRecordWriter> = None;
+    let mut output: Option<String> = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .with_context(|| format!("{} needs a value", name))
+        };
+        match arg.as_str() {
+            "--gauntlet" => gauntlet = Some(value("--gauntlet")?),
+            "--games" => {
+                games_per_pairing = value("--games")?
+                    .parse()
+                    .context("--games needs a number")?
+            }
+            "--first-turn-ms" => {
+                let millis = value("--first-turn-ms")?
+                    .parse()
+                    .context("--first-turn-ms needs milliseconds")?;
+                time_manager.first_turn_limit = Duration::from_millis(millis);
+            }
+            "--turn-ms" => {
+                let millis = value("--turn-ms")?
+                    .parse()
+                    .context("--turn-ms needs milliseconds")?;
+                time_manager.turn_limit = Duration::from_millis(millis);
+            }
+            "--threads" => {
+                threads = Some(
+                    value("--threads")?
+                        .parse()
+                        .context("--threads needs a number")?,
+                )
+            }
+            "--records" => records = Some(JsonLinesGameRecordWriter::create(value("--records")?)?),
+            "--output" => output = Some(value("--output")?),
+            _ if arg.starts_with("--") => bail!("unknown option {}\n{}", arg, usage),
+            _ => players.push(parse_player(&arg)?),
+        }
+    }
+    if players.is_empty() {
+        players = TournamentPlayer::PRESETS
+            .iter()
+            .filter_map(|name| TournamentPlayer::preset(name))
+            .collect();
+    }
+    if players.len() < 2 {
+        bail!("tournament needs at least two players\n{}", usage);
+    }
+    for (index, player) in players.iter().enumerate() {
+        if players[..index]
+            .iter()
+            .any(|other| other.name == player.name)
+        {
+            bail!("player {} is not unique", player.name);
+        }
+    }
+    let pairing = match gauntlet {
+        Some(name) => Pairing::Gauntlet {
+            challenger: players
+                .iter()
+                .position(|player| player.name == name)
+                .with_context(|| format!("gauntlet player {} is no player", name))?,
+        },
+        None => Pairing::RoundRobin,
+    };
+    if let Some(threads) = threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()?;
+    }
+
+    let tournament = Tournament {
+        players,
+        pairing,
+        games_per_pairing,
+        time_manager,
+    };
+    let result = match records {
+        Some(records) => play(&tournament, records),
+        None => play(&tournament, NoGameRecordSink {}),
+    };
+    println!("{}", result);
+    if let Some(output) = output {
+        std::fs::write(&output, format!("{}\n", result))
+            .with_context(|| format!("Failed to write {}", output))?;
+    }
+    Ok(())
+}
+
+fn play<R: GameRecordSink>(tournament: &Tournament, record_sink: R) -> TournamentResult {
+    let num_games = tournament.schedule().len();
+    println!(
+        "{} players, {} games on {} threads",
+        tournament.players.len(),
+        num_games,
+        rayon::current_num_threads()
+    );
+    let finished = AtomicUsize::new(0);
+    let result = tournament.run(record_sink, |game| {
+        let finished = finished.fetch_add(1, Ordering::Relaxed) + 1;
+        eprintln!(
+            "game {}/{}: {} - {} {:.1}",
+            finished,
+            num_games,
+            tournament.players[game.first].name,
+            tournament.players[game.second].name,
+            game.score
+        );
+    });
+    println!();
+    result
+}
+
+// preset name or "name=config.json"
+fn parse_player(arg: &str) -> anyhow::Result<TournamentPlayer> {
+    if let Some((name, path)) = arg.split_once('=') {
+        let json =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+        let config: Config =
+            serde_json::from_str(&json).with_context(|| format!("{} contains no config", path))?;
+        return Ok(TournamentPlayer::new(
+            name,
+            Opponent::HeuristicWithExplorationBoost(config),
+        ));
+    }
+    TournamentPlayer::preset(arg).with_context(|| {
+        format!(
+            "unknown player {}; presets are {}",
+            arg,
+            TournamentPlayer::PRESETS.join(", ")
+        )
+    })
+}
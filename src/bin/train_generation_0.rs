@@ -5,7 +5,7 @@ use std::time::Duration;
 use anyhow::Result;
 use cg_ultimate_tic_tac_toe::{
     ml_linfa::{run_training, ChannelLabelSink, MCTSGenV00, PgLabelWriter},
-    UltTTTMCTSConfig,
+    NoGameRecordSink, UltTTTMCTSConfig,
 };
 use crossbeam::channel;
 use my_lib::my_mcts::{NoHeuristic, PlainMCTS};
@@ -63,6 +63,7 @@ async fn main() -> Result<()> {
         generation,
         min_visits,
         label_sink,
+        NoGameRecordSink {},
         num_matches,
     )?;
 
@@ -0,0 +1,636 @@
+// generic codingame bot: reads input in a parallel thread, ponders during turn of opponent,
+// keeps time budget of each turn and outputs selected moves
+
+use super::{
+    expected_valid_actions, format_action, DecidedBoardPolicy, EndgameSolver, GameValue,
+    OpeningBook, ProtocolError, RootStatistics, RuleSet, TimeManager, TurnInput, TurnTimer, UltTTT,
+    UltTTTMCTSGame, UltTTTMove,
+};
+use anyhow::{bail, Context};
+use my_lib::my_mcts::{Heuristic, MCTSAlgo};
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+use std::io::{self, BufRead, BufReader, Write};
+use std::marker::PhantomData;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub struct BotRunner<A, H> {
+    mcts: A,
+    time_manager: TimeManager,
+    time_out_codingame_input: Duration,
+    // exact solver for late positions; its table is kept across turns and cleared by solver, if
+    // rules of tracked state change after a rebuild
+    endgame_solver: Option<EndgameSolver>,
+    // book moves are played without search
+    opening_book: Option<OpeningBook>,
+    // called once, when own player is known from first input
+    own_player_setup: Option<fn(&mut A, TicTacToeStatus)>,
+    heuristic: PhantomData<H>,
+}
+
+impl<A, H> BotRunner<A, H>
+where
+    H: Heuristic<UltTTTMCTSGame>,
+    A: MCTSAlgo<UltTTTMCTSGame, H> + RootStatistics,
+{
+    pub fn new(mcts: A) -> Self {
+        BotRunner {
+            mcts,
+            time_manager: TimeManager::default(),
+            time_out_codingame_input: Duration::from_millis(60_000),
+            endgame_solver: None,
+            opening_book: None,
+            own_player_setup: None,
+            heuristic: PhantomData,
+        }
+    }
+    pub fn with_time_manager(mut self, time_manager: TimeManager) -> Self {
+        self.time_manager = time_manager;
+        self
+    }
+    pub fn with_endgame_solver(mut self, endgame_solver: EndgameSolver) -> Self {
+        self.endgame_solver = Some(endgame_solver);
+        self
+    }
+    pub fn with_opening_book(mut self, opening_book: OpeningBook) -> Self {
+        self.opening_book = Some(opening_book);
+        self
+    }
+    pub fn with_own_player_setup(mut self, setup: fn(&mut A, TicTacToeStatus)) -> Self {
+        self.own_player_setup = Some(setup);
+        self
+    }
+    // plays on stdin and stdout of codingame
+    pub fn run(self) -> anyhow::Result<()> {
+        self.run_with_io(BufReader::new(io::stdin()), io::stdout())
+            .map(|_| ())
+    }
+    // returns tracked state, when input is closed and move of last input is sent
+    pub fn run_with_io<R, W>(mut self, input: R, mut output: W) -> anyhow::Result<UltTTT>
+    where
+        R: BufRead + Send + 'static,
+        W: Write,
+    {
+        let rx = spawn_input_thread(input);
+        let mut game_data = UltTTT::new();
+        self.mcts.set_root(&game_data);
+
+        // init variables
+        let mut start = Instant::now();
+        let mut turn_counter = 0;
+        let mut first_turn = true;
+        // timer of own turn, which starts with received input
+        let mut turn_timer: Option<TurnTimer> = None;
+        let mut turn_iterations = 0;
+        let mut number_of_iterations = 0;
+        let mut solver_tried = false;
+        let mut solved_move: Option<UltTTTMove> = None;
+        // moves of game and valid actions of codingame after number of moves, from which
+        // game_data is rebuild on desync
+        let mut history: Vec<UltTTTMove> = Vec::new();
+        let mut codingame_valid_actions: Vec<(usize, Vec<UltTTTMove>)> = Vec::new();
+        let mut valid_actions: Vec<UltTTTMove> = Vec::new();
+        eprintln!("Starting pre-filling tree");
+        loop {
+            let input = if game_data.get_status() == TicTacToeStatus::Vacant {
+                rx.try_recv()
+            } else {
+                // game is over: nothing left to search, wait for end of input
+                rx.recv().map_err(|_| mpsc::TryRecvError::Disconnected)
+            };
+            match input {
+                Ok(turn_input) => {
+                    // codingame input received: opponent move or initial input
+                    let turn_input = turn_input?;
+                    let time_elapsed = start.elapsed();
+                    if first_turn {
+                        eprintln!("time of initial input: {:?}", time_elapsed);
+                        // me is start player, if there is no opponent move
+                        let own_player = if turn_input.opponent_move.is_some() {
+                            eprintln!("I'm second player.");
+                            TicTacToeStatus::Second
+                        } else {
+                            eprintln!("I'm first player.");
+                            TicTacToeStatus::First
+                        };
+                        if let Some(setup) = self.own_player_setup {
+                            setup(&mut self.mcts, own_player);
+                        }
+                    } else {
+                        // successive turn
+                        eprintln!("time from opp perspective: {:?}", time_elapsed);
+                    }
+                    if let Some(opponent_move) = turn_input.opponent_move {
+                        turn_counter += 1;
+                        history.push(opponent_move);
+                    }
+                    codingame_valid_actions.push((history.len(), turn_input.valid_actions.clone()));
+                    if let Some(opponent_move) = turn_input.opponent_move {
+                        match game_data.try_apply(&opponent_move) {
+                            Ok(state) => {
+                                game_data = state;
+                                // set root to opponent move
+                                if !self.mcts.set_root(&game_data) {
+                                    eprintln!(
+                                        "Reset root after opponent move in turn {}.",
+                                        turn_counter
+                                    );
+                                }
+                            }
+                            Err(err) => {
+                                // opponent move may be legal with rules of codingame
+                                eprintln!(
+                                    "Opponent move {} is invalid for tracked state: {}",
+                                    format_action(opponent_move),
+                                    err
+                                );
+                                game_data = resync_state(
+                                    &history,
+                                    &codingame_valid_actions,
+                                    game_data.rules(),
+                                )
+                                .with_context(|| {
+                                    format!(
+                                        "Invalid opponent move {}\n{}",
+                                        format_action(opponent_move),
+                                        game_data
+                                    )
+                                })?;
+                                eprintln!("Rebuilt tracked state with {:?}", game_data.rules());
+                                self.mcts.reset_root(&game_data);
+                            }
+                        }
+                    }
+                    if let Err(mismatch) = turn_input.check_valid_actions(&game_data) {
+                        eprintln!(
+                            "Desync of tracked state in turn {}: {}\n{}",
+                            turn_counter, mismatch, game_data
+                        );
+                        match resync_state(&history, &codingame_valid_actions, game_data.rules()) {
+                            Some(state) => {
+                                game_data = state;
+                                eprintln!("Rebuilt tracked state with {:?}", game_data.rules());
+                                self.mcts.reset_root(&game_data);
+                            }
+                            None => eprintln!(
+                                "Rebuild failed; only valid actions of codingame are played."
+                            ),
+                        }
+                    }
+                    valid_actions = turn_input.valid_actions;
+                    // book move is sent with next iteration like a solved move
+                    if let Some(book_move) = self
+                        .opening_book
+                        .as_ref()
+                        .and_then(|opening_book| opening_book.lookup(&game_data))
+                        .filter(|book_move| valid_actions.contains(book_move))
+                    {
+                        eprintln!("Book move: {}", format_action(book_move));
+                        solved_move = Some(book_move);
+                    }
+                    turn_timer = Some(self.time_manager.start_turn(&game_data, first_turn));
+                    turn_iterations = 0;
+                }
+                // input is closed and no move is pending
+                Err(mpsc::TryRecvError::Disconnected) if turn_timer.is_none() => {
+                    return Ok(game_data)
+                }
+                Err(_) => {
+                    // no new input received
+                    if start.elapsed() > self.time_out_codingame_input {
+                        bail!("Timeout while waiting for codingame input");
+                    }
+                    // expand mcts tree until new input is received and
+                    // time_out after received input is reached
+                    self.mcts.iterate();
+                    number_of_iterations += 1;
+                    let Some(timer) = turn_timer.as_mut() else {
+                        // pondering during turn of opponent
+                        continue;
+                    };
+                    turn_iterations += 1;
+                    // try to solve late positions with half of remaining time of turn
+                    if let Some(endgame_solver) = self.endgame_solver.as_mut() {
+                        if !solver_tried && endgame_solver.is_applicable(&game_data) {
+                            solver_tried = true;
+                            let solver_time_out = timer.remaining() / 2;
+                            if let Some(result) = endgame_solver.solve(&game_data, solver_time_out)
+                            {
+                                eprintln!(
+                                    "Solved position: {:?} with {} nodes",
+                                    result.value, result.nodes
+                                );
+                                // a proven loss is left to MCTS, which may choose a move, which is
+                                // harder to answer for a non perfect opponent
+                                if result.value != GameValue::Loss {
+                                    solved_move = Some(result.best_move);
+                                }
+                            }
+                        }
+                    }
+                    if solved_move.is_none()
+                        && !timer.should_stop(turn_iterations, self.mcts.root_leaders())
+                    {
+                        continue;
+                    }
+                    eprintln!("time from my perspective: {:?}", timer.elapsed());
+                    eprintln!("total time of iterations: {:?}", start.elapsed());
+                    turn_counter += 1;
+                    eprintln!(
+                        "Iterations of turn {}: {}",
+                        turn_counter, number_of_iterations
+                    );
+                    // select my move and send it to codingame
+                    let mut selected_move = match solved_move.take() {
+                        Some(solved_move) => solved_move,
+                        None => *self.mcts.select_move(),
+                    };
+                    // never play an illegal move, even if tracked state is out of sync
+                    if !valid_actions.contains(&selected_move) {
+                        eprintln!(
+                            "Selected move {} is no valid action of codingame.",
+                            format_action(selected_move)
+                        );
+                        selected_move = *valid_actions
+                            .first()
+                            .context("codingame sent no valid action")?;
+                    }
+                    game_data.make_move(&selected_move);
+                    history.push(selected_move);
+                    writeln!(output, "{}", format_action(selected_move))?;
+                    output.flush()?;
+                    // set root to my move; root is always found for moves of MCTS, but solved
+                    // or book move may not be expanded in tree
+                    if !self.mcts.set_root(&game_data) {
+                        eprintln!("Reset root after solved move in turn {}.", turn_counter);
+                    }
+                    // reset variables and timer
+                    number_of_iterations = 0;
+                    turn_timer = None;
+                    first_turn = false;
+                    solver_tried = false;
+                    start = Instant::now();
+                }
+            }
+        }
+    }
+}
+
+// A desync of tracked state with codingame is caused by other rules, since all moves of game are
+// known. Moves of game are replayed with each rule set, which changes valid actions, until valid
+// actions of all turns match valid actions, which codingame sent. Tie break is kept, since it
+// does not change valid actions.
+fn resync_state(
+    history: &[UltTTTMove],
+    codingame_valid_actions: &[(usize, Vec<UltTTTMove>)],
+    rules: RuleSet,
+) -> Option<UltTTT> {
+    let mut rule_sets = vec![rules];
+    for tied_boards_count_for_both in [false, true] {
+        for decided_board_policy in [
+            DecidedBoardPolicy::FreeChoice,
+            DecidedBoardPolicy::PlayUntilFull,
+        ] {
+            let candidate = RuleSet {
+                tied_boards_count_for_both,
+                decided_board_policy,
+                ..rules
+            };
+            if !rule_sets.contains(&candidate) {
+                rule_sets.push(candidate);
+            }
+        }
+    }
+    rule_sets
+        .into_iter()
+        .find_map(|rules| replay_with_rules(history, codingame_valid_actions, rules))
+}
+
+fn replay_with_rules(
+    history: &[UltTTTMove],
+    codingame_valid_actions: &[(usize, Vec<UltTTTMove>)],
+    rules: RuleSet,
+) -> Option<UltTTT> {
+    let mut state = UltTTT::with_rules(rules);
+    let mut turns = codingame_valid_actions.iter().peekable();
+    for ply in 0..=history.len() {
+        while let Some((_, valid_actions)) = turns.next_if(|(turn_ply, _)| *turn_ply == ply) {
+            let expected = expected_valid_actions(&state);
+            if expected.len() != valid_actions.len()
+                || valid_actions.iter().any(|mv| !expected.contains(mv))
+            {
+                return None;
+            }
+        }
+        if let Some(mv) = history.get(ply) {
+            state = state.try_apply(mv).ok()?;
+        }
+    }
+    Some(state)
+}
+
+fn spawn_input_thread<R>(mut input: R) -> mpsc::Receiver<Result<TurnInput, ProtocolError>>
+where
+    R: BufRead + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || loop {
+        let turn_input = match TurnInput::read(&mut input) {
+            Ok(Some(turn_input)) => Ok(turn_input),
+            // end of input
+            Ok(None) => break,
+            Err(err) => Err(err),
+        };
+        let is_err = turn_input.is_err();
+        if tx.send(turn_input).is_err() || is_err {
+            break;
+        }
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{expected_valid_actions, parse_action, UltTTTMCTSConfig};
+    use my_lib::my_mcts::{
+        CachedUTC, DefaultSimulationPolicy, DynamicC, ExpandAll, NoHeuristic, NoTranspositionTable,
+        PlainMCTS,
+    };
+    use rand::prelude::SliceRandom;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::io::Read;
+
+    type TestMCTS = PlainMCTS<
+        UltTTTMCTSGame,
+        NoHeuristic,
+        UltTTTMCTSConfig,
+        CachedUTC,
+        NoTranspositionTable,
+        DynamicC,
+        ExpandAll,
+        DefaultSimulationPolicy,
+    >;
+
+    // input of bot, which is written by test as opponent
+    struct ChannelReader {
+        rx: mpsc::Receiver<String>,
+        buffer: Vec<u8>,
+        position: usize,
+    }
+
+    impl Read for ChannelReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.position == self.buffer.len() {
+                match self.rx.recv() {
+                    Ok(text) => {
+                        self.buffer = text.into_bytes();
+                        self.position = 0;
+                    }
+                    // end of input
+                    Err(_) => return Ok(0),
+                }
+            }
+            let len = buf.len().min(self.buffer.len() - self.position);
+            buf[..len].copy_from_slice(&self.buffer[self.position..self.position + len]);
+            self.position += len;
+            Ok(len)
+        }
+    }
+
+    // output of bot, which is send line by line to test
+    struct ChannelWriter {
+        tx: mpsc::Sender<String>,
+        line: Vec<u8>,
+    }
+
+    impl Write for ChannelWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            for &byte in buf {
+                if byte == b'\n' {
+                    let line = String::from_utf8_lossy(&self.line).into_owned();
+                    self.line.clear();
+                    // test may already have stopped reading
+                    let _ = self.tx.send(line);
+                } else {
+                    self.line.push(byte);
+                }
+            }
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn test_runner() -> BotRunner<TestMCTS, NoHeuristic> {
+        let mcts = TestMCTS::new(UltTTTMCTSConfig::default(), NoHeuristic {}, 20_000);
+        BotRunner::new(mcts).with_time_manager(TimeManager::fixed(
+            Duration::from_millis(30),
+            Duration::from_millis(5),
+        ))
+    }
+
+    fn spawn_bot(
+        endgame_solver: Option<EndgameSolver>,
+    ) -> (
+        mpsc::Sender<String>,
+        mpsc::Receiver<String>,
+        thread::JoinHandle<anyhow::Result<UltTTT>>,
+    ) {
+        let mut runner = test_runner();
+        if let Some(endgame_solver) = endgame_solver {
+            runner = runner.with_endgame_solver(endgame_solver);
+        }
+        spawn_runner(runner)
+    }
+
+    fn spawn_runner(
+        runner: BotRunner<TestMCTS, NoHeuristic>,
+    ) -> (
+        mpsc::Sender<String>,
+        mpsc::Receiver<String>,
+        thread::JoinHandle<anyhow::Result<UltTTT>>,
+    ) {
+        let (input_tx, input_rx) = mpsc::channel();
+        let (output_tx, output_rx) = mpsc::channel();
+        let input = BufReader::new(ChannelReader {
+            rx: input_rx,
+            buffer: Vec::new(),
+            position: 0,
+        });
+        let output = ChannelWriter {
+            tx: output_tx,
+            line: Vec::new(),
+        };
+        let handle = thread::spawn(move || runner.run_with_io(input, output));
+        (input_tx, output_rx, handle)
+    }
+
+    // codingame input of one turn
+    fn turn_input(state: &UltTTT, opponent_move: Option<UltTTTMove>) -> String {
+        let opponent_action = match opponent_move {
+            Some(mv) => format_action(mv),
+            None => "-1 -1".to_string(),
+        };
+        let valid_actions = expected_valid_actions(state);
+        let mut input = format!("{}\n{}\n", opponent_action, valid_actions.len());
+        for mv in valid_actions {
+            input.push_str(&format_action(mv));
+            input.push('\n');
+        }
+        input
+    }
+
+    fn receive_move(output_rx: &mpsc::Receiver<String>) -> UltTTTMove {
+        let line = output_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        parse_action(&line).unwrap().unwrap()
+    }
+
+    // returns tracked state of bot and state after last move of bot with rules of codingame
+    fn play_against_random_opponent(
+        bot_is_first: bool,
+        endgame_solver: Option<EndgameSolver>,
+        rules: RuleSet,
+    ) -> (UltTTT, UltTTT) {
+        let mut rng = rand::thread_rng();
+        let (input_tx, output_rx, handle) = spawn_bot(endgame_solver);
+        let mut state = UltTTT::with_rules(rules);
+        let mut opponent_move = None;
+        if !bot_is_first {
+            let mv = *state.legal_moves().as_slice().choose(&mut rng).unwrap();
+            state = state.try_apply(&mv).unwrap();
+            opponent_move = Some(mv);
+        }
+        input_tx.send(turn_input(&state, opponent_move)).unwrap();
+        let bot_state = loop {
+            // move of bot must be legal
+            state = state.try_apply(&receive_move(&output_rx)).unwrap();
+            if state.get_status() != TicTacToeStatus::Vacant {
+                break state;
+            }
+            let bot_state = state;
+            let mv = *state.legal_moves().as_slice().choose(&mut rng).unwrap();
+            state = state.try_apply(&mv).unwrap();
+            if state.get_status() != TicTacToeStatus::Vacant {
+                break bot_state;
+            }
+            input_tx.send(turn_input(&state, Some(mv))).unwrap();
+        };
+        // closing input ends bot
+        drop(input_tx);
+        (handle.join().unwrap().unwrap(), bot_state)
+    }
+
+    #[test]
+    fn test_bot_plays_full_game_as_first_and_second() {
+        let (tracked, bot_state) = play_against_random_opponent(true, None, RuleSet::CODINGAME);
+        assert!(tracked == bot_state);
+        play_against_random_opponent(false, Some(EndgameSolver::new(16)), RuleSet::CODINGAME);
+    }
+
+    #[test]
+    fn test_bot_rebuilds_tracked_state_on_desync() {
+        // bot tracks game with default rules, but codingame keeps won mini boards playable
+        let rules = RuleSet {
+            decided_board_policy: DecidedBoardPolicy::PlayUntilFull,
+            ..RuleSet::CODINGAME
+        };
+        let mut desync = false;
+        for game in 0..10 {
+            let (tracked, bot_state) = play_against_random_opponent(game % 2 == 0, None, rules);
+            assert!(tracked == bot_state);
+            // valid actions of codingame and default rules differ at some turn
+            desync = tracked.rules() == rules;
+            if desync {
+                break;
+            }
+        }
+        assert!(desync);
+    }
+
+    #[test]
+    fn test_resync_state_with_valid_actions_of_codingame() {
+        let rules = RuleSet {
+            decided_board_policy: DecidedBoardPolicy::PlayUntilFull,
+            ..RuleSet::CODINGAME
+        };
+        // random moves until default rules do not match valid actions of codingame
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut state = UltTTT::with_rules(rules);
+        let mut history = Vec::new();
+        let mut codingame_valid_actions = vec![(0, expected_valid_actions(&state))];
+        while replay_with_rules(&history, &codingame_valid_actions, RuleSet::CODINGAME).is_some() {
+            let mv = *state.legal_moves().as_slice().choose(&mut rng).unwrap();
+            state = state.try_apply(&mv).unwrap();
+            history.push(mv);
+            codingame_valid_actions.push((history.len(), expected_valid_actions(&state)));
+        }
+        let resynced =
+            resync_state(&history, &codingame_valid_actions, RuleSet::CODINGAME).unwrap();
+        assert!(resynced == state);
+        // moves contradict valid actions of codingame with every rule set
+        let occupied = history[0];
+        codingame_valid_actions.push((history.len(), vec![occupied]));
+        assert!(resync_state(&history, &codingame_valid_actions, RuleSet::CODINGAME).is_none());
+    }
+
+    #[test]
+    fn test_bot_rejects_invalid_opponent_move() {
+        let (input_tx, output_rx, handle) = spawn_bot(None);
+        let opponent_move = UltTTTMove::try_from((4, 4)).unwrap();
+        let state = UltTTT::new().try_apply(&opponent_move).unwrap();
+        input_tx
+            .send(turn_input(&state, Some(opponent_move)))
+            .unwrap();
+        let state = state.try_apply(&receive_move(&output_rx)).unwrap();
+        // opponent plays occupied cell
+        input_tx
+            .send(turn_input(&state, Some(opponent_move)))
+            .unwrap();
+        let err = handle.join().unwrap().err().unwrap();
+        assert!(err.to_string().contains("Invalid opponent move"));
+    }
+
+    #[test]
+    fn test_bot_plays_only_valid_actions_of_codingame_on_desync() {
+        let (input_tx, output_rx, handle) = spawn_bot(None);
+        // tracked state expects mini board MM, but codingame only allows cell "0 0"
+        input_tx.send("4 4\n1\n0 0\n".to_string()).unwrap();
+        assert_eq!(format_action(receive_move(&output_rx)), "0 0");
+        drop(input_tx);
+        handle.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_bot_plays_book_move() {
+        // search only considers mini board MM for first move of game
+        let book_move = UltTTTMove::try_from((0, 0)).unwrap();
+        let mut opening_book = OpeningBook::new();
+        opening_book.insert(&UltTTT::new(), book_move);
+        let (input_tx, output_rx, handle) =
+            spawn_runner(test_runner().with_opening_book(opening_book));
+        input_tx.send(turn_input(&UltTTT::new(), None)).unwrap();
+        assert_eq!(receive_move(&output_rx), book_move);
+        // search continues after book move
+        let state = UltTTT::new().try_apply(&book_move).unwrap();
+        let opponent_move = state.legal_moves().as_slice()[0];
+        let state = state.try_apply(&opponent_move).unwrap();
+        input_tx
+            .send(turn_input(&state, Some(opponent_move)))
+            .unwrap();
+        assert!(state.try_apply(&receive_move(&output_rx)).is_ok());
+        drop(input_tx);
+        handle.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_bot_reports_protocol_error() {
+        let (input_tx, _output_rx, handle) = spawn_bot(None);
+        input_tx.send("4 x\n0\n".to_string()).unwrap();
+        let err = handle.join().unwrap().err().unwrap();
+        assert!(err.downcast_ref::<ProtocolError>().is_some());
+    }
+}
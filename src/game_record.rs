@@ -0,0 +1,386 @@
+// record of played matches, which can be stored as JSON lines and replayed
+
+use super::{player_from_notation, player_to_notation, MoveError, UltTTT, UltTTTMove};
+use anyhow::{Context, Result};
+use crossbeam::channel;
+use my_lib::my_mcts::{HeuristicConfig, MCTSConfig, NoHeuristic};
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// parameters of a config by parameter name (see Config::parameter_names())
+pub type ConfigRecord = BTreeMap<String, f64>;
+
+// parameters of a heuristic config, which are not defined by HeuristicConfig
+pub trait AdditionalParameters: HeuristicConfig {
+    fn additional_parameters(&self) -> Vec<(String, f64)>;
+}
+
+impl AdditionalParameters for NoHeuristic {
+    fn additional_parameters(&self) -> Vec<(String, f64)> {
+        Vec::new()
+    }
+}
+
+// collect all parameters, which are defined by MCTSConfig and HeuristicConfig, and additional
+// parameters of heuristic config
+pub fn config_record<MC, HC>(mcts_config: &MC, heuristic_config: &HC) -> ConfigRecord
+where
+    MC: MCTSConfig<TicTacToeStatus>,
+    HC: AdditionalParameters,
+{
+    let mut record: ConfigRecord = [
+        (
+            "exploration_constant",
+            mcts_config.exploration_constant() as f64,
+        ),
+        (
+            "exploration_boost_first",
+            mcts_config.exploration_boost(TicTacToeStatus::First) as f64,
+        ),
+        (
+            "exploration_boost_second",
+            mcts_config.exploration_boost(TicTacToeStatus::Second) as f64,
+        ),
+        (
+            "progressive_widening_constant",
+            mcts_config.progressive_widening_constant() as f64,
+        ),
+        (
+            "progressive_widening_exponent",
+            mcts_config.progressive_widening_exponent() as f64,
+        ),
+        (
+            "early_cut_off_depth",
+            mcts_config.early_cut_off_depth() as f64,
+        ),
+        (
+            "progressive_widening_initial_threshold",
+            heuristic_config.progressive_widening_initial_threshold() as f64,
+        ),
+        (
+            "progressive_widening_decay_rate",
+            heuristic_config.progressive_widening_decay_rate() as f64,
+        ),
+        (
+            "early_cut_off_lower_bound",
+            heuristic_config.early_cut_off_lower_bound() as f64,
+        ),
+        (
+            "early_cut_off_upper_bound",
+            heuristic_config.early_cut_off_upper_bound() as f64,
+        ),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_owned(), value))
+    .collect();
+    record.extend(heuristic_config.additional_parameters());
+    record
+}
+
+// one ply of a match in codingame coordinates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MoveRecord {
+    pub row: u8,
+    pub col: u8,
+    // time, which player used to search for this move
+    pub time_ms: f64,
+    // number of MCTS iterations of player to search for this move
+    pub iterations: usize,
+}
+
+impl MoveRecord {
+    pub fn new(mv: UltTTTMove, time: Duration, iterations: usize) -> Self {
+        let (col, row) = <(u8, u8)>::from(mv);
+        MoveRecord {
+            row,
+            col,
+            time_ms: time.as_secs_f64() * 1_000.0,
+            iterations,
+        }
+    }
+    pub fn to_move(&self) -> Result<UltTTTMove, MoveError> {
+        UltTTTMove::try_from((self.col, self.row))
+    }
+}
+
+// First always uses first_config, Second always uses second_config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    #[serde(with = "player_serde")]
+    pub start_player: TicTacToeStatus,
+    pub moves: Vec<MoveRecord>,
+    // score from perspective of First: 1.0 win, 0.5 tie, 0.0 loss; None if match is not finished
+    pub result: Option<f64>,
+    pub first_config: ConfigRecord,
+    pub second_config: ConfigRecord,
+}
+
+impl GameRecord {
+    pub fn new(
+        start_player: TicTacToeStatus,
+        first_config: ConfigRecord,
+        second_config: ConfigRecord,
+    ) -> Self {
+        GameRecord {
+            start_player,
+            moves: Vec::new(),
+            result: None,
+            first_config,
+            second_config,
+        }
+    }
+    pub fn push_move(&mut self, mv: UltTTTMove, time: Duration, iterations: usize) {
+        self.moves.push(MoveRecord::new(mv, time, iterations));
+    }
+    pub fn initial_state(&self) -> UltTTT {
+        let mut state = UltTTT::new();
+        state.set_current_player(self.start_player);
+        state
+    }
+    // yields state after each ply; stops after first illegal move
+    pub fn replay(&self) -> GameReplay<'_> {
+        GameReplay {
+            moves: self.moves.iter(),
+            state: self.initial_state(),
+            failed: false,
+        }
+    }
+    pub fn to_json_line(&self) -> Result<String> {
+        serde_json::to_string(self).context("Failed to serialize game record to JSON")
+    }
+    pub fn from_json_line(line: &str) -> Result<Self> {
+        serde_json::from_str(line).context("Failed to deserialize game record from JSON")
+    }
+    pub fn read_json_lines<P: AsRef<Path>>(path: P) -> Result<Vec<Self>> {
+        let file = File::open(path.as_ref())
+            .with_context(|| format!("Failed to open {}", path.as_ref().display()))?;
+        BufReader::new(file)
+            .lines()
+            .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .map(|line| GameRecord::from_json_line(&line?))
+            .collect()
+    }
+}
+
+pub struct GameReplay<'a> {
+    moves: std::slice::Iter<'a, MoveRecord>,
+    state: UltTTT,
+    failed: bool,
+}
+
+impl Iterator for GameReplay<'_> {
+    type Item = Result<UltTTT, MoveError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let move_record = self.moves.next()?;
+        match move_record
+            .to_move()
+            .and_then(|mv| self.state.try_apply(&mv))
+        {
+            Ok(state) => {
+                self.state = state;
+                Some(Ok(state))
+            }
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+// start player is stored in same format as in position notation
+mod player_serde {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        player: &TicTacToeStatus,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&player_to_notation(*player).to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<TicTacToeStatus, D::Error> {
+        let player = String::deserialize(deserializer)?;
+        player_from_notation(&player).map_err(serde::de::Error::custom)
+    }
+}
+
+// trait for GameRecord-Sink
+pub trait GameRecordSink: Clone + Send + Sync {
+    fn insert(&mut self, game_record: GameRecord) -> Result<()>;
+}
+
+// use this sink, if records should be dropped
+#[derive(Clone, Copy, Default)]
+pub struct NoGameRecordSink {}
+
+impl GameRecordSink for NoGameRecordSink {
+    fn insert(&mut self, _game_record: GameRecord) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct ChannelGameRecordSink {
+    tx: channel::Sender<GameRecord>,
+}
+
+impl ChannelGameRecordSink {
+    pub fn new(tx: channel::Sender<GameRecord>) -> Self {
+        Self { tx }
+    }
+}
+
+impl GameRecordSink for ChannelGameRecordSink {
+    fn insert(&mut self, game_record: GameRecord) -> Result<()> {
+        self.tx
+            .send(game_record)
+            .map_err(|e| anyhow::anyhow!("send failed: {:?}", e))
+    }
+}
+
+// appends each record as one JSON line to file; clones share the same file
+#[derive(Clone)]
+pub struct JsonLinesGameRecordWriter {
+    writer: Arc<Mutex<BufWriter<File>>>,
+}
+
+impl JsonLinesGameRecordWriter {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path.as_ref())
+            .with_context(|| format!("Failed to open {}", path.as_ref().display()))?;
+        Ok(Self {
+            writer: Arc::new(Mutex::new(BufWriter::new(file))),
+        })
+    }
+}
+
+impl GameRecordSink for JsonLinesGameRecordWriter {
+    fn insert(&mut self, game_record: GameRecord) -> Result<()> {
+        let line = game_record.to_json_line()?;
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|_| anyhow::anyhow!("game record writer is poisoned"))?;
+        writeln!(writer, "{}", line)?;
+        // flush each record, so that records survive an aborted run
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::Config;
+    use crate::{UltTTTHeuristicConfig, UltTTTMCTSConfig, UltTTTMCTSGame};
+    use my_lib::my_mcts::{GameCache, MCTSGame, NoGameCache};
+    use rand::prelude::SliceRandom;
+
+    fn random_game_record(start_player: TicTacToeStatus) -> (GameRecord, Vec<UltTTT>) {
+        let mut rng = rand::thread_rng();
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        let config = Config {
+            mcts: UltTTTMCTSConfig::new_optimized(),
+            heuristic: UltTTTHeuristicConfig::new_optimized(),
+        };
+        let mut game_record = GameRecord::new(
+            start_player,
+            config.parameter_map(),
+            config_record(&config.mcts, &config.heuristic),
+        );
+        let mut state = game_record.initial_state();
+        let mut states = Vec::new();
+        while UltTTTMCTSGame::evaluate(&state, &mut game_cache).is_none() {
+            let moves: Vec<UltTTTMove> = UltTTTMCTSGame::available_moves(&state).collect();
+            let mv = *moves.choose(&mut rng).unwrap();
+            state = UltTTTMCTSGame::apply_move(&state, &mv, &mut game_cache);
+            game_record.push_move(mv, Duration::from_millis(85), 1_000);
+            states.push(state);
+        }
+        game_record.result = UltTTTMCTSGame::evaluate(&state, &mut game_cache).map(|s| s as f64);
+        (game_record, states)
+    }
+
+    fn assert_config_record_eq(left: &ConfigRecord, right: &ConfigRecord) {
+        assert!(left.keys().eq(right.keys()));
+        assert!(left
+            .values()
+            .zip(right.values())
+            .all(|(l, r)| (l - r).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_json_line_round_trip_and_replay() {
+        for start_player in [TicTacToeStatus::First, TicTacToeStatus::Second] {
+            let (game_record, states) = random_game_record(start_player);
+            let line = game_record.to_json_line().unwrap();
+            assert!(!line.contains('\n'));
+            let loaded = GameRecord::from_json_line(&line).unwrap();
+            assert_eq!(loaded.start_player, game_record.start_player);
+            assert_eq!(loaded.moves, game_record.moves);
+            assert_eq!(loaded.result, game_record.result);
+            // serde_json may change last digit of floats
+            assert_config_record_eq(&loaded.first_config, &game_record.first_config);
+            assert_config_record_eq(&loaded.second_config, &game_record.second_config);
+            let replayed: Vec<UltTTT> = loaded.replay().collect::<Result<_, _>>().unwrap();
+            assert_eq!(replayed.len(), states.len());
+            assert!(replayed.iter().zip(states.iter()).all(|(r, s)| r == s));
+            assert_config_record_eq(
+                &Config::deserialize(serde_json::to_value(&loaded.first_config).unwrap())
+                    .unwrap()
+                    .parameter_map(),
+                &loaded.first_config,
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_record_of_config() {
+        let config = Config {
+            mcts: UltTTTMCTSConfig::new_optimized(),
+            heuristic: UltTTTHeuristicConfig::new_optimized(),
+        };
+        let record = config_record(&config.mcts, &config.heuristic);
+        let mut names = Config::parameter_names();
+        names.sort();
+        assert!(record.keys().eq(names.iter()));
+        let values: Vec<f64> = config.clone().into();
+        for (name, value) in Config::parameter_names().iter().zip(values) {
+            assert_eq!(record[name], value);
+        }
+        // without UltTTTHeuristicConfig only parameters of MCTSConfig and HeuristicConfig
+        let record = config_record(&config.mcts, &NoHeuristic {});
+        assert_eq!(record.len(), 10);
+        assert!(record
+            .keys()
+            .all(|name| Config::parameter_names().contains(name)));
+    }
+
+    #[test]
+    fn test_replay_stops_at_illegal_move() {
+        let (mut game_record, _) = random_game_record(TicTacToeStatus::First);
+        // repeat first move, which occupies an already occupied cell
+        let first_move = game_record.moves[0].clone();
+        game_record.moves.insert(2, first_move);
+        let replayed: Vec<Result<UltTTT, MoveError>> = game_record.replay().collect();
+        assert_eq!(replayed.len(), 3);
+        assert!(replayed[..2].iter().all(|r| r.is_ok()));
+        assert!(replayed[2].is_err());
+    }
+}
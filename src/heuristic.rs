@@ -1,11 +1,17 @@
 // heuristic of UltTTT
 
-use super::{NextActionConstraint, UltTTT, UltTTTHeuristicConfig, UltTTTMCTSGame, UltTTTMove};
+use super::{
+    with_match_rng, NextActionConstraint, UltTTT, UltTTTHeuristicConfig, UltTTTMCTSGame, UltTTTMove,
+};
 use my_lib::{
     my_map_3x3::CellIndex3x3,
-    my_mcts::{Heuristic, HeuristicCache, MCTSGame, NoHeuristicCache},
+    my_mcts::{
+        Heuristic, HeuristicCache, HeuristicCutoff, MCTSConfig, MCTSGame, NoHeuristicCache,
+        SimulationPolicy,
+    },
     my_tic_tac_toe::TicTacToeStatus,
 };
+use rand::prelude::SliceRandom;
 use std::collections::HashSet;
 
 #[derive(Clone)]
@@ -91,7 +97,7 @@ impl Heuristic<UltTTTMCTSGame> for UltTTTHeuristic {
 
     fn evaluate_state(
         state: &<UltTTTMCTSGame as MCTSGame>::State,
-        game_cache: &mut <UltTTTMCTSGame as MCTSGame>::Cache,
+        _game_cache: &mut <UltTTTMCTSGame as MCTSGame>::Cache,
         heuristic_cache: &mut Self::Cache,
         perspective_player: Option<<UltTTTMCTSGame as MCTSGame>::Player>,
         heuristic_config: &Self::Config,
@@ -109,12 +115,13 @@ impl Heuristic<UltTTTMCTSGame> for UltTTTHeuristic {
                 1.0 - score
             };
         }
-        let score = match UltTTTMCTSGame::evaluate(state, game_cache) {
+        // terminal and already decided states get their exact value
+        let score = match state
+            .decided_outcome()
+            .and_then(|outcome| outcome.evaluate())
+        {
             Some(value) => value,
             None => {
-                // ToDo: if status map contains won cells and vacant cells cannot change win status of player with
-                // most won cells, than this is a win for player of most won cells. Try to get this in heuristic.
-
                 // mini board control, weighted with cell_weight
                 let mut first_control_sum = 0.0;
                 let mut second_control_sum = 0.0;
@@ -124,9 +131,10 @@ impl Heuristic<UltTTTMCTSGame> for UltTTTHeuristic {
                 let mut second_threat_sum = 0.0;
 
                 // threats on status map
-                let (first_meta_threats, second_meta_threats) = state.status_map.get_threats();
+                let status_map = state.get_status_map();
+                let (first_meta_threats, second_meta_threats) = status_map.get_threats();
 
-                for (status_index, status) in state.status_map.iter_map() {
+                for (status_index, status) in status_map.iter_map() {
                     match status {
                         TicTacToeStatus::Tie => {
                             // tie mini board, no control, no threats
@@ -142,9 +150,9 @@ impl Heuristic<UltTTTMCTSGame> for UltTTTHeuristic {
                         }
                         TicTacToeStatus::Vacant => {
                             // vacant mini board: get control and threats
+                            let mini_board = state.get_mini_board(status_index);
                             // mini board control
-                            let (first_control, second_control) =
-                                state.map.get_cell(status_index).get_board_control();
+                            let (first_control, second_control) = mini_board.get_board_control();
                             let first_control_score = UltTTTHeuristic::normalized_tanh(
                                 first_control,
                                 second_control,
@@ -155,8 +163,7 @@ impl Heuristic<UltTTTMCTSGame> for UltTTTHeuristic {
                             second_control_sum += second_control_score * status_index.cell_weight();
 
                             // mini board threats
-                            let (first_threats, second_threats) =
-                                state.map.get_cell(status_index).get_threats();
+                            let (first_threats, second_threats) = mini_board.get_threats();
                             // cell weight
                             let cell_weight = status_index.cell_weight();
                             // meta factors
@@ -165,7 +172,7 @@ impl Heuristic<UltTTTMCTSGame> for UltTTTHeuristic {
                                 num_first_meta_small_threats,
                                 num_second_meta_threats,
                                 num_second_meta_small_threats,
-                            ) = state.status_map.get_meta_cell_threats(status_index);
+                            ) = status_map.get_meta_cell_threats(status_index);
                             let first_meta_factor = 1.0
                                 + heuristic_config.meta_cell_big_threat
                                     * num_first_meta_threats as f32
@@ -247,7 +254,7 @@ impl Heuristic<UltTTTMCTSGame> for UltTTTHeuristic {
                 }
 
                 // meta progress: wins on status_map
-                let played_cells = state.status_map.count_non_vacant_cells();
+                let played_cells = status_map.count_non_vacant_cells();
 
                 // calculate heuristic value
                 let progress = played_cells as f32 / 9.0;
@@ -300,4 +307,67 @@ impl Heuristic<UltTTTMCTSGame> for UltTTTHeuristic {
             heuristic_config,
         )
     }
+
+    // same as default of Heuristic, but moves of equal value are shuffled with match rng
+    fn sort_moves(
+        state: &<UltTTTMCTSGame as MCTSGame>::State,
+        moves: Vec<<UltTTTMCTSGame as MCTSGame>::Move>,
+        game_cache: &mut <UltTTTMCTSGame as MCTSGame>::Cache,
+        heuristic_cache: &mut Self::Cache,
+        heuristic_config: &Self::Config,
+    ) -> Vec<(f32, <UltTTTMCTSGame as MCTSGame>::Move)> {
+        let mut heuristic_moves: Vec<(f32, UltTTTMove)> = moves
+            .into_iter()
+            .map(|mv| {
+                (
+                    Self::evaluate_move(state, &mv, game_cache, heuristic_cache, heuristic_config),
+                    mv,
+                )
+            })
+            .collect();
+        with_match_rng(|rng| heuristic_moves.shuffle(rng));
+        heuristic_moves
+            .sort_unstable_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        heuristic_moves
+    }
+}
+
+// returns exact value for already decided states, otherwise delegates to HeuristicCutoff
+#[derive(Clone)]
+pub struct DecidedOutcomeCutoff {}
+
+impl<H, Config> SimulationPolicy<UltTTTMCTSGame, H, Config> for DecidedOutcomeCutoff
+where
+    H: Heuristic<UltTTTMCTSGame>,
+    Config: MCTSConfig<TicTacToeStatus>,
+{
+    fn should_cutoff(
+        state: &UltTTT,
+        depth: usize,
+        game_cache: &mut <UltTTTMCTSGame as MCTSGame>::Cache,
+        heuristic_cache: &mut H::Cache,
+        perspective_player: Option<TicTacToeStatus>,
+        mcts_config: &Config,
+        heuristic_config: &H::Config,
+    ) -> Option<f32> {
+        if let Some(score) = state
+            .decided_outcome()
+            .and_then(|outcome| outcome.evaluate())
+        {
+            // score is from perspective of first
+            return match perspective_player.unwrap_or(state.last_player) {
+                TicTacToeStatus::First => Some(score),
+                _ => Some(1.0 - score),
+            };
+        }
+        <HeuristicCutoff as SimulationPolicy<UltTTTMCTSGame, H, Config>>::should_cutoff(
+            state,
+            depth,
+            game_cache,
+            heuristic_cache,
+            perspective_player,
+            mcts_config,
+            heuristic_config,
+        )
+    }
 }
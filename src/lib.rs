@@ -6,43 +6,112 @@ pub use config::*;
 pub mod caching;
 pub use caching::*;
 
+pub mod match_rng;
+pub use match_rng::*;
+
 pub mod heuristic;
 pub use heuristic::*;
 
+pub mod zobrist;
+pub use zobrist::*;
+
+pub mod symmetry;
+pub use symmetry::*;
+
+pub mod move_list;
+pub use move_list::*;
+
+pub mod progressive_widening;
+pub use progressive_widening::*;
+
+pub mod notation;
+pub use notation::*;
+
+pub mod game_record;
+pub use game_record::*;
+
+pub mod move_error;
+pub use move_error::*;
+
+pub mod rules;
+pub use rules::*;
+
+pub mod solver;
+pub use solver::*;
+
+pub mod outcome;
+
+pub mod make_unmake;
+pub use make_unmake::*;
+
+pub mod perft;
+pub use perft::*;
+
+pub mod protocol;
+pub use protocol::*;
+
+pub mod opening_book;
+pub use opening_book::*;
+
+pub mod time_manager;
+pub use time_manager::*;
+
+pub mod bot_runner;
+pub use bot_runner::*;
+
+pub mod analysis;
+pub use analysis::*;
+
+pub mod arena;
+pub use arena::*;
+
+pub mod sprt;
+pub use sprt::*;
+
 pub mod utilities;
 
+pub mod opponent;
+pub use opponent::*;
+
+pub mod tournament;
+pub use tournament::*;
+
 pub mod ml_linfa;
 
 mod old_heuristic;
 
 use my_lib::my_map_3x3::*;
-use my_lib::my_mcts::{
-    GamePlayer, HeuristicProgressiveWidening, MCTSGame, NoGameCache, ProgressiveWidening,
-};
+use my_lib::my_mcts::{GamePlayer, MCTSGame, NoGameCache, ProgressiveWidening};
 use my_lib::my_tic_tac_toe::*;
 
 use std::cmp::Ordering;
 use std::convert::TryFrom;
 use std::fmt::Display;
 use std::fmt::Write;
+use std::hash::{Hash, Hasher};
 
-#[derive(Copy, Clone, PartialEq, Default)]
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
 pub struct UltTTTMove {
     pub status_index: CellIndex3x3,
     pub mini_board_index: CellIndex3x3,
 }
 
 impl TryFrom<(u8, u8)> for UltTTTMove {
-    type Error = ();
+    type Error = MoveError;
     fn try_from(cg_coordinates: (u8, u8)) -> Result<Self, Self::Error> {
+        if cg_coordinates.0 > 8 || cg_coordinates.1 > 8 {
+            return Err(MoveError::OutOfRange(cg_coordinates));
+        }
         let x_status = cg_coordinates.0 / 3;
         let y_status = cg_coordinates.1 / 3;
         let x_mini_board = cg_coordinates.0 % 3;
         let y_mini_board = cg_coordinates.1 % 3;
 
         Ok(UltTTTMove {
-            status_index: CellIndex3x3::try_from((x_status, y_status))?,
-            mini_board_index: CellIndex3x3::try_from((x_mini_board, y_mini_board))?,
+            status_index: CellIndex3x3::try_from((x_status, y_status))
+                .map_err(|_| MoveError::OutOfRange(cg_coordinates))?,
+            mini_board_index: CellIndex3x3::try_from((x_mini_board, y_mini_board))
+                .map_err(|_| MoveError::OutOfRange(cg_coordinates))?,
         })
     }
 }
@@ -88,36 +157,223 @@ pub enum NextActionConstraint {
     MiniBoard(CellIndex3x3),
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
+// bitboard layout: cell (status_index, mini_board_index) is bit 9 * status_index + mini_board_index
+// of the 81 bit occupancy mask of each player. Mini board bits of status map use index of status cell.
+const MINI_BOARD_MASK: u16 = 0b1_1111_1111;
+const LINE_MASKS: [u16; 8] = [
+    0b000_000_111,
+    0b000_111_000,
+    0b111_000_000,
+    0b001_001_001,
+    0b010_010_010,
+    0b100_100_100,
+    0b100_010_001,
+    0b001_010_100,
+];
+// WIN_TABLE[board] is true, if 9 bit board contains at least one full line
+const WIN_TABLE: [bool; 512] = build_win_table();
+const CELL_INDICES: [CellIndex3x3; 9] = [
+    CellIndex3x3::TL,
+    CellIndex3x3::TM,
+    CellIndex3x3::TR,
+    CellIndex3x3::ML,
+    CellIndex3x3::MM,
+    CellIndex3x3::MR,
+    CellIndex3x3::BL,
+    CellIndex3x3::BM,
+    CellIndex3x3::BR,
+];
+
+const fn build_win_table() -> [bool; 512] {
+    let mut table = [false; 512];
+    let mut board = 0;
+    while board < 512 {
+        let mut line = 0;
+        while line < LINE_MASKS.len() {
+            if board as u16 & LINE_MASKS[line] == LINE_MASKS[line] {
+                table[board] = true;
+            }
+            line += 1;
+        }
+        board += 1;
+    }
+    table
+}
+
+// iterates indices of set bits from lowest to highest bit
+struct BitIter(u16);
+
+impl Iterator for BitIter {
+    type Item = usize;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(index)
+    }
+}
+
+fn player_index(player: TicTacToeStatus) -> usize {
+    match player {
+        TicTacToeStatus::First => 0,
+        TicTacToeStatus::Second => 1,
+        _ => unreachable!("Player is always First or Second"),
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Default)]
 pub struct UltTTT {
-    map: MyMap3x3<TicTacToeGameData>,
-    status_map: TicTacToeGameData,
+    // occupancy of all 81 cells for First and Second
+    cells: [u128; 2],
+    // mini boards won by First and Second
+    won_boards: [u16; 2],
+    // mini boards, which are closed for further moves (see DecidedBoardPolicy)
+    closed_boards: u16,
     next_action_constraint: NextActionConstraint,
     current_player: TicTacToeStatus,
     last_player: TicTacToeStatus,
+    // incrementally updated zobrist key of cells, next_action_constraint and current_player
+    zobrist: u64,
+    rules: RuleSet,
+}
+
+// equal states always have equal zobrist keys
+impl Hash for UltTTT {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.zobrist);
+    }
 }
 
 impl UltTTT {
     pub fn new() -> Self {
         UltTTT {
-            map: MyMap3x3::new(),
-            status_map: TicTacToeGameData::new(),
+            cells: [0; 2],
+            won_boards: [0; 2],
+            closed_boards: 0,
             next_action_constraint: NextActionConstraint::Init,
             current_player: TicTacToeStatus::First,
             last_player: TicTacToeStatus::First,
+            zobrist: ZOBRIST_KEYS.constraint(NextActionConstraint::Init),
+            rules: RuleSet::default(),
         }
     }
     pub fn set_current_player(&mut self, player: TicTacToeStatus) {
+        if player != self.current_player {
+            self.zobrist ^= ZOBRIST_KEYS.second_to_move;
+        }
         self.current_player = player;
     }
     pub fn next_player(&mut self) {
         self.last_player = self.current_player;
         self.current_player = self.current_player.next();
+        self.zobrist ^= ZOBRIST_KEYS.second_to_move;
+    }
+    pub fn zobrist(&self) -> u64 {
+        self.zobrist
+    }
+    // full computation of zobrist key, used to verify incremental updates
+    pub fn calc_zobrist(&self) -> u64 {
+        let mut zobrist = ZOBRIST_KEYS.constraint(self.next_action_constraint);
+        if self.current_player == TicTacToeStatus::Second {
+            zobrist ^= ZOBRIST_KEYS.second_to_move;
+        }
+        for (player, player_keys) in ZOBRIST_KEYS.cells.iter().enumerate() {
+            for (cell, key) in player_keys.iter().enumerate() {
+                if self.cells[player] & (1 << cell) != 0 {
+                    zobrist ^= key;
+                }
+            }
+        }
+        zobrist
+    }
+    // status of meta board
+    pub fn get_status(&self) -> TicTacToeStatus {
+        match (self.wins_meta_board(0), self.wins_meta_board(1)) {
+            // only possible, if tied boards count for both: last move completed both lines
+            (true, true) => self.last_player,
+            (true, false) => TicTacToeStatus::First,
+            (false, true) => TicTacToeStatus::Second,
+            (false, false) if self.closed_boards == MINI_BOARD_MASK => TicTacToeStatus::Tie,
+            (false, false) => TicTacToeStatus::Vacant,
+        }
+    }
+    // status map as TicTacToeGameData, e.g. for heuristic analysis
+    pub fn get_status_map(&self) -> TicTacToeGameData {
+        let mut status_map = TicTacToeGameData::new();
+        for (index, status_index) in CELL_INDICES.iter().enumerate() {
+            status_map.set_cell_value(*status_index, self.get_mini_board_status(index));
+        }
+        status_map
+    }
+    // mini board as TicTacToeGameData, e.g. for heuristic analysis
+    pub fn get_mini_board(&self, status_index: CellIndex3x3) -> TicTacToeGameData {
+        let mut mini_board = TicTacToeGameData::new();
+        for mini_board_index in CELL_INDICES.iter() {
+            mini_board.set_cell_value(
+                *mini_board_index,
+                self.get_cell_value(UltTTTMove {
+                    status_index,
+                    mini_board_index: *mini_board_index,
+                }),
+            );
+        }
+        mini_board
+    }
+    fn get_mini_board_status(&self, status_index: usize) -> TicTacToeStatus {
+        let bit = 1 << status_index;
+        if self.won_boards[0] & bit != 0 {
+            TicTacToeStatus::First
+        } else if self.won_boards[1] & bit != 0 {
+            TicTacToeStatus::Second
+        } else if self.closed_boards & bit != 0 {
+            TicTacToeStatus::Tie
+        } else {
+            TicTacToeStatus::Vacant
+        }
+    }
+    fn get_mini_board_bits(&self, player_index: usize, status_index: usize) -> u16 {
+        (self.cells[player_index] >> (9 * status_index)) as u16 & MINI_BOARD_MASK
+    }
+    fn get_vacant_cells(&self, status_index: usize) -> u16 {
+        !(self.get_mini_board_bits(0, status_index) | self.get_mini_board_bits(1, status_index))
+            & MINI_BOARD_MASK
+    }
+    fn get_open_boards(&self) -> u16 {
+        match self.next_action_constraint {
+            NextActionConstraint::Init => 1 << usize::from(CellIndex3x3::MM),
+            NextActionConstraint::MiniBoard(constraint) => 1 << usize::from(constraint),
+            NextActionConstraint::None => !self.closed_boards & MINI_BOARD_MASK,
+        }
+    }
+    pub fn legal_moves(&self) -> MoveList {
+        let mut move_list = MoveList::new();
+        for status_index in BitIter(self.get_open_boards()) {
+            for mini_board_index in BitIter(self.get_vacant_cells(status_index)) {
+                move_list.push(UltTTTMove {
+                    status_index: CELL_INDICES[status_index],
+                    mini_board_index: CELL_INDICES[mini_board_index],
+                });
+            }
+        }
+        move_list
+    }
+    pub fn legal_move_count(&self) -> usize {
+        BitIter(self.get_open_boards())
+            .map(|status_index| self.get_vacant_cells(status_index).count_ones() as usize)
+            .sum()
     }
     fn get_cell_value(&self, cell: UltTTTMove) -> TicTacToeStatus {
-        self.map
-            .get_cell(cell.status_index)
-            .get_cell_value(cell.mini_board_index)
+        let bit =
+            1u128 << (9 * usize::from(cell.status_index) + usize::from(cell.mini_board_index));
+        if self.cells[0] & bit != 0 {
+            TicTacToeStatus::First
+        } else if self.cells[1] & bit != 0 {
+            TicTacToeStatus::Second
+        } else {
+            TicTacToeStatus::Vacant
+        }
     }
 }
 
@@ -154,8 +410,7 @@ impl Display for UltTTT {
 }
 
 pub type HPWDefaultTTTWithGameCache = ProgressiveWidening<UltTTTMCTSGame, UltTTTMCTSConfig>;
-pub type HPWDefaultTTTNoGameCache =
-    HeuristicProgressiveWidening<UltTTTMCTSGame, UltTTTHeuristic, UltTTTMCTSConfig>;
+pub type HPWDefaultTTTNoGameCache = UltTTTProgressiveWidening<UltTTTHeuristic, UltTTTMCTSConfig>;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct UltTTTMCTSGame {}
@@ -166,39 +421,10 @@ impl MCTSGame for UltTTTMCTSGame {
     type Player = TicTacToeStatus;
     type Cache = NoGameCache<UltTTT, UltTTTMove>;
 
+    // moves are generated into a MoveList on the stack; its iterator has an exact size hint, so
+    // that progressive widening collects moves with a single allocation
     fn available_moves<'a>(state: &'a Self::State) -> Box<dyn Iterator<Item = Self::Move> + 'a> {
-        match state.next_action_constraint {
-            NextActionConstraint::Init => Box::new(
-                state
-                    .map
-                    .get_cell(CellIndex3x3::MM)
-                    .iter_map()
-                    .filter_map(|(i, c)| UltTTTMove::valid_move(CellIndex3x3::MM, i, c)),
-            ),
-            NextActionConstraint::MiniBoard(constraint) => Box::new(
-                state
-                    .map
-                    .get_cell(constraint)
-                    .iter_map()
-                    .filter_map(move |(i, c)| UltTTTMove::valid_move(constraint, i, c)),
-            ),
-            NextActionConstraint::None => Box::new(
-                state
-                    .status_map
-                    .iter_map()
-                    .filter_map(|(i, c)| match c {
-                        TicTacToeStatus::Vacant => Some(i),
-                        _ => None,
-                    })
-                    .flat_map(move |status_cell| {
-                        state
-                            .map
-                            .get_cell(status_cell)
-                            .iter_map()
-                            .filter_map(move |(i, c)| UltTTTMove::valid_move(status_cell, i, c))
-                    }),
-            ),
-        }
+        Box::new(state.legal_moves().into_iter())
     }
 
     fn apply_move(
@@ -207,39 +433,17 @@ impl MCTSGame for UltTTTMCTSGame {
         _game_cache: &mut Self::Cache,
     ) -> Self::State {
         let mut new_state = *state;
-        // apply the move for current player
-        new_state
-            .map
-            .get_cell_mut(mv.status_index)
-            .set_cell_value(mv.mini_board_index, state.current_player);
-        let status = new_state.map.get_cell(mv.status_index).get_status();
-        if !status.is_vacant() {
-            new_state.status_map.set_cell_value(mv.status_index, status);
-        }
-
-        // player_move.mini_board_index points to next TicTacToe for next player to set new value.
-        // if this TicTacToe status is not vacant (meaning there are no more cells to set), player can choose from all free cells
-        new_state.next_action_constraint = if new_state
-            .status_map
-            .get_cell_value(mv.mini_board_index)
-            .is_vacant()
-        {
-            NextActionConstraint::MiniBoard(mv.mini_board_index)
-        } else {
-            NextActionConstraint::None
-        };
-        // set the next player
-        new_state.next_player();
+        new_state.make_move(mv);
         new_state
     }
 
     fn evaluate(state: &Self::State, _game_cache: &mut Self::Cache) -> Option<f32> {
-        let mut status = state.status_map.get_status();
-        if status == TicTacToeStatus::Tie {
+        let mut status = state.get_status();
+        if status == TicTacToeStatus::Tie && state.rules.tie_break == TieBreak::CountWonBoards {
             // game finished without direct winner
             // count for each player number of won squares; most squares won wins game
-            let my_squares = state.status_map.count_me_cells();
-            let opp_squares = state.status_map.count_opp_cells();
+            let my_squares = state.won_boards[0].count_ones();
+            let opp_squares = state.won_boards[1].count_ones();
             status = match my_squares.cmp(&opp_squares) {
                 Ordering::Greater => TicTacToeStatus::First,
                 Ordering::Less => TicTacToeStatus::Second,
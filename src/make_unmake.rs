@@ -0,0 +1,245 @@
+// in place application and reversal of moves for deep searches, which do not need copies of state
+
+use super::{
+    player_index, DecidedBoardPolicy, NextActionConstraint, UltTTT, UltTTTMove, WIN_TABLE,
+    ZOBRIST_KEYS,
+};
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+
+// everything, which is needed to restore state before move
+#[derive(Copy, Clone, PartialEq)]
+pub struct UndoInfo {
+    cell: usize,
+    player: usize,
+    won_boards: [u16; 2],
+    closed_boards: u16,
+    next_action_constraint: NextActionConstraint,
+    current_player: TicTacToeStatus,
+    last_player: TicTacToeStatus,
+    zobrist: u64,
+}
+
+impl UltTTT {
+    // applies legal move of current player; state after move is identical to apply_move()
+    pub fn make_move(&mut self, mv: &UltTTTMove) -> UndoInfo {
+        let player = player_index(self.current_player);
+        let status_index = usize::from(mv.status_index);
+        let cell = 9 * status_index + usize::from(mv.mini_board_index);
+        let undo = UndoInfo {
+            cell,
+            player,
+            won_boards: self.won_boards,
+            closed_boards: self.closed_boards,
+            next_action_constraint: self.next_action_constraint,
+            current_player: self.current_player,
+            last_player: self.last_player,
+            zobrist: self.zobrist,
+        };
+        self.cells[player] |= 1 << cell;
+        self.zobrist ^= ZOBRIST_KEYS.cells[player][cell];
+        let status_bit = 1 << status_index;
+        // with DecidedBoardPolicy::PlayUntilFull a won mini board keeps its owner
+        let is_won = (self.won_boards[0] | self.won_boards[1]) & status_bit != 0;
+        if !is_won && WIN_TABLE[self.get_mini_board_bits(player, status_index) as usize] {
+            self.won_boards[player] |= status_bit;
+            if self.rules.decided_board_policy == DecidedBoardPolicy::FreeChoice {
+                self.closed_boards |= status_bit;
+            }
+        }
+        if self.get_vacant_cells(status_index) == 0 {
+            self.closed_boards |= status_bit;
+        }
+
+        // player_move.mini_board_index points to next TicTacToe for next player to set new value.
+        // if this TicTacToe status is not vacant (meaning there are no more cells to set), player can choose from all free cells
+        let next_action_constraint =
+            if self.closed_boards & (1 << usize::from(mv.mini_board_index)) == 0 {
+                NextActionConstraint::MiniBoard(mv.mini_board_index)
+            } else {
+                NextActionConstraint::None
+            };
+        self.zobrist ^= ZOBRIST_KEYS.constraint(self.next_action_constraint)
+            ^ ZOBRIST_KEYS.constraint(next_action_constraint);
+        self.next_action_constraint = next_action_constraint;
+        // set the next player
+        self.next_player();
+        undo
+    }
+    // reverts move, which returned undo; moves must be unmade in reverse order
+    pub fn unmake_move(&mut self, undo: UndoInfo) {
+        self.cells[undo.player] &= !(1 << undo.cell);
+        self.won_boards = undo.won_boards;
+        self.closed_boards = undo.closed_boards;
+        self.next_action_constraint = undo.next_action_constraint;
+        self.current_player = undo.current_player;
+        self.last_player = undo.last_player;
+        self.zobrist = undo.zobrist;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RuleSet, UltTTTMCTSGame, CELL_INDICES};
+    use my_lib::my_map_3x3::CellIndex3x3;
+    use my_lib::my_mcts::{GameCache, GamePlayer, MCTSGame, NoGameCache};
+    use my_lib::my_tic_tac_toe::TicTacToeGameData;
+    use rand::prelude::SliceRandom;
+
+    const RULE_SETS: [RuleSet; 4] = [
+        RuleSet::CODINGAME,
+        RuleSet::CLASSIC,
+        RuleSet::TIED_BOARDS_COUNT_FOR_BOTH,
+        RuleSet {
+            decided_board_policy: DecidedBoardPolicy::PlayUntilFull,
+            ..RuleSet::CODINGAME
+        },
+    ];
+
+    // reference game of TicTacToeGameData, which is independent of bitboards of UltTTT: wins and
+    // ties of mini boards are decided by TicTacToeGameData::get_status()
+    #[derive(Clone)]
+    struct ReferenceGame {
+        mini_boards: [TicTacToeGameData; 9],
+        status_map: TicTacToeGameData,
+        next_board: Option<CellIndex3x3>,
+        current_player: TicTacToeStatus,
+        rules: RuleSet,
+    }
+
+    impl ReferenceGame {
+        fn new(rules: RuleSet) -> Self {
+            ReferenceGame {
+                mini_boards: [TicTacToeGameData::new(); 9],
+                status_map: TicTacToeGameData::new(),
+                next_board: Some(CellIndex3x3::MM),
+                current_player: TicTacToeStatus::First,
+                rules,
+            }
+        }
+        fn is_closed(&self, status_index: CellIndex3x3) -> bool {
+            match self.rules.decided_board_policy {
+                DecidedBoardPolicy::FreeChoice => {
+                    self.status_map.get_cell_value(status_index).is_not_vacant()
+                }
+                DecidedBoardPolicy::PlayUntilFull => {
+                    self.mini_boards[usize::from(status_index)].count_non_vacant_cells() == 9
+                }
+            }
+        }
+        fn legal_moves(&self) -> Vec<UltTTTMove> {
+            let open_boards: Vec<CellIndex3x3> = match self.next_board {
+                Some(status_index) => vec![status_index],
+                None => CELL_INDICES
+                    .into_iter()
+                    .filter(|status_index| !self.is_closed(*status_index))
+                    .collect(),
+            };
+            open_boards
+                .into_iter()
+                .flat_map(|status_index| {
+                    self.mini_boards[usize::from(status_index)]
+                        .iter_map()
+                        .filter(|(_, cell)| cell.is_vacant())
+                        .map(move |(mini_board_index, _)| UltTTTMove {
+                            status_index,
+                            mini_board_index,
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        }
+        fn apply(&mut self, mv: &UltTTTMove) {
+            let mini_board = &mut self.mini_boards[usize::from(mv.status_index)];
+            mini_board.set_cell_value(mv.mini_board_index, self.current_player);
+            // first winner keeps mini board
+            if self.status_map.get_cell_value(mv.status_index).is_vacant() {
+                self.status_map
+                    .set_cell_value(mv.status_index, mini_board.get_status());
+            }
+            self.next_board = if self.is_closed(mv.mini_board_index) {
+                None
+            } else {
+                Some(mv.mini_board_index)
+            };
+            self.current_player = self.current_player.next();
+        }
+        fn assert_matches(&self, state: &UltTTT) {
+            for status_index in CELL_INDICES {
+                assert!(
+                    state.get_mini_board(status_index)
+                        == self.mini_boards[usize::from(status_index)]
+                );
+            }
+            assert!(state.get_status_map() == self.status_map);
+            assert_eq!(state.legal_moves().as_slice(), &self.legal_moves()[..]);
+            assert_eq!(state.current_player, self.current_player);
+            assert_eq!(state.zobrist(), state.calc_zobrist());
+            if self.rules.decided_board_policy == DecidedBoardPolicy::FreeChoice
+                && !self.rules.tied_boards_count_for_both
+            {
+                assert_eq!(state.get_status(), self.status_map.get_status());
+            }
+        }
+    }
+
+    #[test]
+    fn test_make_unmake_round_trip_matches_apply_move() {
+        let mut rng = rand::thread_rng();
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        for i in 0..400 {
+            let rules = RULE_SETS[i % RULE_SETS.len()];
+            let mut state = UltTTT::with_rules(rules);
+            let mut reference = ReferenceGame::new(rules);
+            while UltTTTMCTSGame::evaluate(&state, &mut game_cache).is_none() {
+                reference.assert_matches(&state);
+                let moves: Vec<UltTTTMove> = state.legal_moves().into_iter().collect();
+                // every legal move round trips
+                for mv in moves.iter() {
+                    let before = state;
+                    let undo = state.make_move(mv);
+                    let mut reference_after = reference.clone();
+                    reference_after.apply(mv);
+                    reference_after.assert_matches(&state);
+                    assert!(state == UltTTTMCTSGame::apply_move(&before, mv, &mut game_cache));
+                    // notation parser recomputes status map from cells; with PlayUntilFull
+                    // notation cannot tell, who won a mini board first
+                    if state.rules().decided_board_policy == DecidedBoardPolicy::FreeChoice {
+                        let notation = state.to_notation();
+                        let parsed =
+                            UltTTT::from_notation_with_rules(&notation, state.rules()).unwrap();
+                        assert!(state == parsed);
+                    }
+                    state.unmake_move(undo);
+                    assert!(state == before);
+                }
+                let mv = moves.choose(&mut rng).unwrap();
+                state.make_move(mv);
+                reference.apply(mv);
+            }
+            reference.assert_matches(&state);
+        }
+    }
+
+    #[test]
+    fn test_unmake_full_game_in_reverse_order() {
+        let mut rng = rand::thread_rng();
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        for i in 0..400 {
+            let initial_state = UltTTT::with_rules(RULE_SETS[i % RULE_SETS.len()]);
+            let mut state = initial_state;
+            let mut history: Vec<(UltTTT, UndoInfo)> = Vec::new();
+            while UltTTTMCTSGame::evaluate(&state, &mut game_cache).is_none() {
+                let moves: Vec<UltTTTMove> = state.legal_moves().into_iter().collect();
+                let mv = moves.choose(&mut rng).unwrap();
+                let before = state;
+                history.push((before, state.make_move(mv)));
+            }
+            while let Some((before, undo)) = history.pop() {
+                state.unmake_move(undo);
+                assert!(state == before);
+            }
+            assert!(state == initial_state);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+// random numbers of search, seeded per match for reproducible matches
+// Search policies of this crate draw random numbers with with_match_rng(). While a match rng is
+// seeded on the current thread, all random numbers come from it; otherwise thread_rng() is used.
+// Both MCTS instances of a match run on the same thread and share its match rng, therefore a match
+// with a deterministic budget and a given seed always replays the same game.
+// Simulation of MCTS draws playout moves from thread_rng(); engines of matches use MatchRngPlayout
+// instead, which plays out with match rng. Likewise ExpandAll shuffles moves with thread_rng(),
+// therefore engines of matches use MatchRngExpandAll.
+
+use super::{UltTTT, UltTTTMCTSGame, UltTTTMove};
+use my_lib::my_mcts::{ExpansionPolicy, Heuristic, MCTSConfig, MCTSGame, SimulationPolicy};
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+use rand::prelude::{IteratorRandom, SliceRandom};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+use std::cell::RefCell;
+use std::marker::PhantomData;
+
+thread_local! {
+    static MATCH_RNG: RefCell<Option<StdRng>> = const { RefCell::new(None) };
+}
+
+// restores previous match rng of thread on drop
+pub struct MatchRngGuard {
+    previous: Option<StdRng>,
+}
+
+impl Drop for MatchRngGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        MATCH_RNG.with(|rng| *rng.borrow_mut() = previous);
+    }
+}
+
+// seeds match rng of current thread until guard is dropped
+#[must_use = "match rng is reset, if guard is dropped"]
+pub fn seed_match_rng(seed: u64) -> MatchRngGuard {
+    let previous = MATCH_RNG.with(|rng| rng.borrow_mut().replace(StdRng::seed_from_u64(seed)));
+    MatchRngGuard { previous }
+}
+
+// f must not call with_match_rng() itself
+pub fn with_match_rng<T>(f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+    MATCH_RNG.with(|rng| match rng.borrow_mut().as_mut() {
+        Some(match_rng) => f(match_rng),
+        None => f(&mut rand::thread_rng()),
+    })
+}
+
+// random playout with moves of match rng until SP cuts off or game ends; SP only cuts off
+#[derive(Clone)]
+pub struct MatchRngPlayout<SP> {
+    phantom: PhantomData<SP>,
+}
+
+impl<H, Config, SP> SimulationPolicy<UltTTTMCTSGame, H, Config> for MatchRngPlayout<SP>
+where
+    H: Heuristic<UltTTTMCTSGame>,
+    Config: MCTSConfig<TicTacToeStatus>,
+    SP: SimulationPolicy<UltTTTMCTSGame, H, Config>,
+{
+    fn should_cutoff(
+        state: &UltTTT,
+        depth: usize,
+        game_cache: &mut <UltTTTMCTSGame as MCTSGame>::Cache,
+        heuristic_cache: &mut H::Cache,
+        perspective_player: Option<TicTacToeStatus>,
+        mcts_config: &Config,
+        heuristic_config: &H::Config,
+    ) -> Option<f32> {
+        let mut state = *state;
+        let mut depth = depth;
+        loop {
+            if let Some(score) = SP::should_cutoff(
+                &state,
+                depth,
+                game_cache,
+                heuristic_cache,
+                perspective_player,
+                mcts_config,
+                heuristic_config,
+            ) {
+                return Some(score);
+            }
+            let mv = with_match_rng(|rng| UltTTTMCTSGame::available_moves(&state).choose(rng))
+                .expect("No available moves");
+            state = UltTTTMCTSGame::apply_move(&state, &mv, game_cache);
+            depth += 1;
+            // terminal score is from perspective of first, same as in simulation of MCTS
+            if let Some(score) = UltTTTMCTSGame::evaluate(&state, game_cache) {
+                return Some(score);
+            }
+        }
+    }
+}
+
+// expands all moves at once in order of match rng, same as ExpandAll
+#[derive(Clone)]
+pub struct MatchRngExpandAll {}
+
+impl<H, Config> ExpansionPolicy<UltTTTMCTSGame, H, Config> for MatchRngExpandAll
+where
+    H: Heuristic<UltTTTMCTSGame>,
+    Config: MCTSConfig<TicTacToeStatus>,
+{
+    fn new(
+        _state: &UltTTT,
+        _game_cache: &mut <UltTTTMCTSGame as MCTSGame>::Cache,
+        _heuristic_cache: &mut H::Cache,
+        _heuristic_config: &H::Config,
+    ) -> Self {
+        MatchRngExpandAll {}
+    }
+    fn expandable_moves(
+        &mut self,
+        _visits: usize,
+        _num_parent_children: usize,
+        state: &UltTTT,
+        _mcts_config: &Config,
+        _heuristic_config: &H::Config,
+    ) -> Vec<UltTTTMove> {
+        let mut moves: Vec<UltTTTMove> = UltTTTMCTSGame::available_moves(state).collect();
+        with_match_rng(|rng| moves.shuffle(rng));
+        moves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_seeded_match_rng_replays() {
+        let draw = || with_match_rng(|rng| (0..8).map(|_| rng.gen::<u32>()).collect::<Vec<_>>());
+        let first = {
+            let _guard = seed_match_rng(7);
+            draw()
+        };
+        let replay = {
+            let _guard = seed_match_rng(7);
+            let replay = draw();
+            // nested seeds restore outer rng
+            {
+                let _inner = seed_match_rng(8);
+                assert_ne!(draw(), first);
+            }
+            assert_ne!(draw(), first);
+            replay
+        };
+        assert_eq!(first, replay);
+        // without seed thread_rng is used
+        assert_ne!(draw(), first);
+    }
+}
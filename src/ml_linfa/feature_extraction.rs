@@ -3,10 +3,12 @@
 use blake3::Hasher;
 use my_lib::my_tic_tac_toe::TicTacToeStatus;
 
-use crate::{NextActionConstraint, UltTTT};
+use crate::{NextActionConstraint, Symmetry, UltTTT};
 
 pub trait FeatureExtraction {
     fn extract_features(&self) -> Vec<f64>;
+    // features of canonical representative of all symmetric states
+    fn extract_canonical_features(&self) -> Vec<f64>;
 }
 
 impl FeatureExtraction for UltTTT {
@@ -14,18 +16,18 @@ impl FeatureExtraction for UltTTT {
         let mut features: Vec<f64> = Vec::with_capacity(91);
 
         // values of 81 cells
-        for (_, cell_value) in self
-            .status_map
-            .iter_map()
-            .flat_map(|(c, _)| self.map.get_cell(c).iter_map())
-        {
-            let feature = match cell_value {
-                TicTacToeStatus::Vacant => 0.0,
-                TicTacToeStatus::First => 1.0,
-                TicTacToeStatus::Second => -1.0,
-                TicTacToeStatus::Tie => panic!("TicTacToeStatus::Tie in Mini Board not allowed"),
-            };
-            features.push(feature);
+        for (status_index, _) in self.get_status_map().iter_map() {
+            for (_, cell_value) in self.get_mini_board(status_index).iter_map() {
+                let feature = match cell_value {
+                    TicTacToeStatus::Vacant => 0.0,
+                    TicTacToeStatus::First => 1.0,
+                    TicTacToeStatus::Second => -1.0,
+                    TicTacToeStatus::Tie => {
+                        panic!("TicTacToeStatus::Tie in Mini Board not allowed")
+                    }
+                };
+                features.push(feature);
+            }
         }
 
         // Constraint: One-Hot over 10 fields (MiniBoard 0–8 + "free choice" at index 9)
@@ -39,6 +41,30 @@ impl FeatureExtraction for UltTTT {
 
         features
     }
+    fn extract_canonical_features(&self) -> Vec<f64> {
+        self.canonical().0.extract_features()
+    }
+}
+
+// features of all distinct symmetric states of a state with given features of UltTTT, e.g. to
+// augment training data of canonical labels. Features are permuted without rebuilding state.
+pub fn augment_features(features: &[f64]) -> Vec<Vec<f64>> {
+    let mut augmented: Vec<Vec<f64>> = Vec::with_capacity(Symmetry::ALL.len());
+    for symmetry in Symmetry::ALL {
+        let mut transformed = features.to_vec();
+        for (cell, feature) in features[..81].iter().enumerate() {
+            let status_index = symmetry.transform_index(cell / 9);
+            transformed[9 * status_index + symmetry.transform_index(cell % 9)] = *feature;
+        }
+        // index 9 of constraint encoding is free choice, which is not transformed
+        for (board, feature) in features[81..90].iter().enumerate() {
+            transformed[81 + symmetry.transform_index(board)] = *feature;
+        }
+        if !augmented.contains(&transformed) {
+            augmented.push(transformed);
+        }
+    }
+    augmented
 }
 
 pub fn hash_features(features: &[f64]) -> i64 {
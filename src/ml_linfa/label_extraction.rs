@@ -1,6 +1,6 @@
 // extract labels from MCTS tree
 
-use super::{hash_features, FeatureExtraction};
+use super::{augment_features, hash_features, FeatureExtraction};
 use anyhow::{Context, Result};
 use my_lib::my_mcts::{
     DfsWalker, Heuristic, MCTSAlgo, MCTSGame, MCTSNode, MCTSTree, NoTranspositionTable,
@@ -18,6 +18,23 @@ pub struct LabeledExample {
     pub features: Vec<f64>,
 }
 
+impl LabeledExample {
+    // examples of all distinct symmetric states, e.g. to augment training data; all of them keep
+    // hash of canonical features
+    pub fn augmented(&self) -> Vec<LabeledExample> {
+        augment_features(&self.features)
+            .into_iter()
+            .map(|features| LabeledExample {
+                hash: self.hash,
+                generation: self.generation,
+                visits: self.visits,
+                score: self.score,
+                features,
+            })
+            .collect()
+    }
+}
+
 // trait for Label-Sink
 pub trait LabelSink: Clone + Send + Sync {
     fn insert(&mut self, labeled_example: LabeledExample) -> Result<()>;
@@ -55,7 +72,8 @@ where
         let node = tree.get_node(node_id);
         let visits = node.get_visits();
         if visits >= min_visits {
-            let features = node.get_state().extract_features();
+            // symmetric states share canonical features and therefore hash
+            let features = node.get_state().extract_canonical_features();
             let hash = hash_features(&features);
             let generation =
                 i64::try_from(generation).context("generation value too large for i64")?;
@@ -1,13 +1,17 @@
 // setting up matches to collect learning data for linfa
 
 use super::{collect_labels, LabelSink};
-use crate::{UltTTT, UltTTTMCTSConfig, UltTTTMCTSGame};
+use crate::{
+    config_record, AdditionalParameters, GameRecord, GameRecordSink, UltTTT, UltTTTMCTSConfig,
+    UltTTTMCTSGame,
+};
 use anyhow::Result;
 use my_lib::my_mcts::{
     ExpansionPolicy, Heuristic, MCTSAlgo, MCTSGame, NoTranspositionTable, PlainMCTS, PruneToRoot,
     SimulationPolicy, UCTPolicy, UTCCache,
 };
 use my_lib::my_optimizer::SharedError;
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
 use rayon::prelude::*;
 use std::sync::{
     atomic::{AtomicUsize, Ordering},
@@ -28,22 +32,36 @@ type MatchResult<H, UC, UP, EP, SP> = (
     UltMCTSTraining<H, UC, UP, EP, SP>,
 );
 
-pub fn run_training_match<H, UC, UP, EP, SP, S>(
+pub fn run_training_match<H, UC, UP, EP, SP, S, R>(
     mut first_mcts_ult_ttt: UltMCTSTraining<H, UC, UP, EP, SP>,
     mut second_mcts_ult_ttt: UltMCTSTraining<H, UC, UP, EP, SP>,
     turn_duration: Duration,
     generation: u32,
     min_visits: usize,
     label_sink: S,
+    mut record_sink: R,
 ) -> Result<MatchResult<H, UC, UP, EP, SP>>
 where
     H: Heuristic<UltTTTMCTSGame>,
+    H::Config: AdditionalParameters,
     UC: UTCCache<UltTTTMCTSGame, UP, UltTTTMCTSConfig>,
     UP: UCTPolicy<UltTTTMCTSGame, UltTTTMCTSConfig>,
     EP: ExpansionPolicy<UltTTTMCTSGame, H, UltTTTMCTSConfig>,
     SP: SimulationPolicy<UltTTTMCTSGame, H, UltTTTMCTSConfig>,
     S: LabelSink,
+    R: GameRecordSink,
 {
+    let mut game_record = GameRecord::new(
+        TicTacToeStatus::First,
+        config_record(
+            &first_mcts_ult_ttt.mcts_config,
+            &first_mcts_ult_ttt.heuristic_config,
+        ),
+        config_record(
+            &second_mcts_ult_ttt.mcts_config,
+            &second_mcts_ult_ttt.heuristic_config,
+        ),
+    );
     let mut first_ult_ttt_game_data = UltTTT::new();
     let mut second_ult_ttt_game_data = UltTTT::new();
 
@@ -83,10 +101,13 @@ where
 
             tracing::debug!(turn_counter, "Starting iteration of first.");
             let start = Instant::now();
+            let mut iterations = 0;
             while start.elapsed() < turn_duration {
                 first_mcts_ult_ttt.iterate();
+                iterations += 1;
             }
             let selected_move = *first_mcts_ult_ttt.select_move();
+            game_record.push_move(selected_move, start.elapsed(), iterations);
             first_ult_ttt_game_data = UltTTTMCTSGame::apply_move(
                 &first_ult_ttt_game_data,
                 &selected_move,
@@ -124,10 +145,13 @@ where
 
             tracing::debug!(turn_counter, "Starting iteration of second.");
             let start = Instant::now();
+            let mut iterations = 0;
             while start.elapsed() < turn_duration {
                 second_mcts_ult_ttt.iterate();
+                iterations += 1;
             }
             let selected_move = *second_mcts_ult_ttt.select_move();
+            game_record.push_move(selected_move, start.elapsed(), iterations);
             second_ult_ttt_game_data = UltTTTMCTSGame::apply_move(
                 &second_ult_ttt_game_data,
                 &selected_move,
@@ -141,30 +165,34 @@ where
             first = true;
         }
     }
-    Ok((
+    let score =
         UltTTTMCTSGame::evaluate(&first_ult_ttt_game_data, &mut first_mcts_ult_ttt.game_cache)
-            .unwrap() as f64,
-        first_mcts_ult_ttt,
-        second_mcts_ult_ttt,
-    ))
+            .unwrap() as f64;
+    game_record.result = Some(score);
+    record_sink.insert(game_record)?;
+    Ok((score, first_mcts_ult_ttt, second_mcts_ult_ttt))
 }
 
-pub fn run_training<H, UC, UP, EP, SP, S>(
+#[allow(clippy::too_many_arguments)]
+pub fn run_training<H, UC, UP, EP, SP, S, R>(
     first_mcts_ult_ttt: UltMCTSTraining<H, UC, UP, EP, SP>,
     second_mcts_ult_ttt: UltMCTSTraining<H, UC, UP, EP, SP>,
     turn_duration: Duration,
     generation: u32,
     min_visits: usize,
     label_sink: S,
+    record_sink: R,
     num_matches: usize,
 ) -> Result<()>
 where
     H: Heuristic<UltTTTMCTSGame>,
+    H::Config: AdditionalParameters,
     UC: UTCCache<UltTTTMCTSGame, UP, UltTTTMCTSConfig>,
     UP: UCTPolicy<UltTTTMCTSGame, UltTTTMCTSConfig>,
     EP: ExpansionPolicy<UltTTTMCTSGame, H, UltTTTMCTSConfig>,
     SP: SimulationPolicy<UltTTTMCTSGame, H, UltTTTMCTSConfig>,
     S: LabelSink,
+    R: GameRecordSink,
 {
     let match_counter = Arc::new(AtomicUsize::new(1));
     let shared_error = SharedError::new();
@@ -188,6 +216,7 @@ where
             generation,
             min_visits,
             label_sink.clone(),
+            record_sink.clone(),
         ) {
             Ok(res) => res,
             Err(e) => {
@@ -0,0 +1,150 @@
+// checked application of moves with typed rule violations
+
+use super::{NextActionConstraint, UltTTT, UltTTTMCTSGame, UltTTTMove};
+use my_lib::my_map_3x3::CellIndex3x3;
+use my_lib::my_mcts::{GameCache, MCTSGame, NoGameCache};
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveError {
+    // codingame coordinates (x, y) are not inside of 9x9 board
+    OutOfRange((u8, u8)),
+    OccupiedCell(UltTTTMove),
+    WrongMiniBoard {
+        expected: CellIndex3x3,
+        actual: CellIndex3x3,
+    },
+    BoardAlreadyDecided(CellIndex3x3),
+    GameOver,
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveError::OutOfRange((x, y)) => {
+                write!(f, "coordinates x {} y {} are out of range", x, y)
+            }
+            MoveError::OccupiedCell(mv) => write!(
+                f,
+                "cell {:?} of mini board {:?} is already occupied",
+                mv.mini_board_index, mv.status_index
+            ),
+            MoveError::WrongMiniBoard { expected, actual } => write!(
+                f,
+                "move must be played in mini board {:?}, not in {:?}",
+                expected, actual
+            ),
+            MoveError::BoardAlreadyDecided(status_index) => {
+                write!(f, "mini board {:?} is already decided", status_index)
+            }
+            MoveError::GameOver => write!(f, "game is already over"),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+impl UltTTT {
+    // checks move against rules of game; in contrast to legal_moves() the first move may
+    // be played in any mini board
+    pub fn check_move(&self, mv: &UltTTTMove) -> Result<(), MoveError> {
+        if self.get_status() != TicTacToeStatus::Vacant {
+            return Err(MoveError::GameOver);
+        }
+        if let NextActionConstraint::MiniBoard(expected) = self.next_action_constraint {
+            if expected != mv.status_index {
+                return Err(MoveError::WrongMiniBoard {
+                    expected,
+                    actual: mv.status_index,
+                });
+            }
+        }
+        if self.closed_boards & (1 << usize::from(mv.status_index)) != 0 {
+            return Err(MoveError::BoardAlreadyDecided(mv.status_index));
+        }
+        if self.get_cell_value(*mv) != TicTacToeStatus::Vacant {
+            return Err(MoveError::OccupiedCell(*mv));
+        }
+        Ok(())
+    }
+    pub fn try_apply(&self, mv: &UltTTTMove) -> Result<UltTTT, MoveError> {
+        self.check_move(mv)?;
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        Ok(UltTTTMCTSGame::apply_move(self, mv, &mut game_cache))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::SliceRandom;
+
+    #[test]
+    fn test_out_of_range() {
+        assert_eq!(
+            UltTTTMove::try_from((9, 0)),
+            Err(MoveError::OutOfRange((9, 0)))
+        );
+        assert_eq!(
+            UltTTTMove::try_from((3, 12)),
+            Err(MoveError::OutOfRange((3, 12)))
+        );
+    }
+
+    #[test]
+    fn test_rule_violations() {
+        let mv = |x: u8, y: u8| UltTTTMove::try_from((x, y)).unwrap();
+        let state = UltTTT::new().try_apply(&mv(4, 4)).unwrap();
+        assert_eq!(
+            state.try_apply(&mv(4, 4)).err(),
+            Some(MoveError::OccupiedCell(mv(4, 4)))
+        );
+        assert_eq!(
+            state.try_apply(&mv(0, 0)).err(),
+            Some(MoveError::WrongMiniBoard {
+                expected: CellIndex3x3::MM,
+                actual: CellIndex3x3::TL,
+            })
+        );
+        // mini board TL is won by First and Second has free choice
+        let state = UltTTT::from_notation("X8/1X7/2X6/9/4O4/9/9/9/8O O X *").unwrap();
+        assert_eq!(
+            state.try_apply(&mv(0, 1)).err(),
+            Some(MoveError::BoardAlreadyDecided(CellIndex3x3::TL))
+        );
+        assert!(state.try_apply(&mv(3, 3)).is_ok());
+    }
+
+    #[test]
+    fn test_try_apply_accepts_exactly_legal_moves() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let mut state = UltTTT::new();
+            loop {
+                if state.get_status() != TicTacToeStatus::Vacant {
+                    assert_eq!(
+                        state.try_apply(&UltTTTMove::default()).err(),
+                        Some(MoveError::GameOver)
+                    );
+                    break;
+                }
+                let legal_moves = state.legal_moves();
+                // legal_moves() restricts first move to mini board MM by choice, not by rules
+                let is_init = state.next_action_constraint == NextActionConstraint::Init;
+                for x in 0..9 {
+                    for y in 0..9 {
+                        let mv = UltTTTMove::try_from((x, y)).unwrap();
+                        assert_eq!(
+                            state.try_apply(&mv).is_ok(),
+                            is_init || legal_moves.contains(&mv)
+                        );
+                    }
+                }
+                let mv = legal_moves.as_slice().choose(&mut rng).unwrap();
+                state = state.try_apply(mv).unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+// fixed capacity move list of UltTTT, which lives on the stack
+
+use super::UltTTTMove;
+
+// UltTTT has at most 81 legal moves
+pub const MAX_NUM_MOVES: usize = 81;
+
+#[derive(Copy, Clone)]
+pub struct MoveList {
+    moves: [UltTTTMove; MAX_NUM_MOVES],
+    len: usize,
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MoveList {
+    pub fn new() -> Self {
+        MoveList {
+            moves: [UltTTTMove::default(); MAX_NUM_MOVES],
+            len: 0,
+        }
+    }
+    pub fn push(&mut self, mv: UltTTTMove) {
+        assert!(self.len < MAX_NUM_MOVES, "MoveList is full.");
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn as_slice(&self) -> &[UltTTTMove] {
+        &self.moves[..self.len]
+    }
+    pub fn as_mut_slice(&mut self) -> &mut [UltTTTMove] {
+        &mut self.moves[..self.len]
+    }
+    pub fn iter(&self) -> std::slice::Iter<'_, UltTTTMove> {
+        self.as_slice().iter()
+    }
+    pub fn contains(&self, mv: &UltTTTMove) -> bool {
+        self.as_slice().contains(mv)
+    }
+}
+
+pub struct MoveListIntoIter {
+    move_list: MoveList,
+    index: usize,
+}
+
+impl Iterator for MoveListIntoIter {
+    type Item = UltTTTMove;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == self.move_list.len {
+            return None;
+        }
+        let mv = self.move_list.moves[self.index];
+        self.index += 1;
+        Some(mv)
+    }
+    // exact size hint: collect() allocates exactly once
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.move_list.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for MoveListIntoIter {}
+
+impl IntoIterator for MoveList {
+    type Item = UltTTTMove;
+    type IntoIter = MoveListIntoIter;
+    fn into_iter(self) -> Self::IntoIter {
+        MoveListIntoIter {
+            move_list: self,
+            index: 0,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a UltTTTMove;
+    type IntoIter = std::slice::Iter<'a, UltTTTMove>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -0,0 +1,343 @@
+// compact textual notation of UltTTT positions
+//
+// format: "<cells> <current player> <last player> <constraint>"
+// cells: 9 rows of the 9x9 board (same orientation as Display), separated by '/'.
+//        'X' is First, 'O' is Second, digits 1-9 are number of consecutive vacant cells.
+// players: 'X' or 'O'
+// constraint: '-' for Init (no move played yet), '*' for free choice of mini board,
+//             or index 0-8 of mini board, in which next move must be played.
+//
+// example: initial position: "9/9/9/9/9/9/9/9/9 X X -"
+
+use super::{
+    DecidedBoardPolicy, NextActionConstraint, RuleSet, UltTTT, UltTTTMove, CELL_INDICES, WIN_TABLE,
+};
+use my_lib::my_map_3x3::CellIndex3x3;
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotationError {
+    WrongNumberOfFields(usize),
+    WrongNumberOfRows(usize),
+    InvalidRow(usize),
+    InvalidCell(char),
+    InvalidPlayer(String),
+    InvalidConstraint(String),
+    MiniBoardWonByBoth(CellIndex3x3),
+    MetaBoardWonByBoth,
+    ConstraintToClosedMiniBoard(CellIndex3x3),
+    InitConstraintWithOccupiedCells,
+    CurrentPlayerEqualsLastPlayer,
+    InvalidNumberOfCells { first: u32, second: u32 },
+}
+
+impl Display for NotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotationError::WrongNumberOfFields(n) => {
+                write!(f, "expected 4 fields separated by whitespace, got {}", n)
+            }
+            NotationError::WrongNumberOfRows(n) => write!(f, "expected 9 rows, got {}", n),
+            NotationError::InvalidRow(row) => {
+                write!(f, "row {} does not describe exactly 9 cells", row)
+            }
+            NotationError::InvalidCell(c) => write!(f, "invalid cell character '{}'", c),
+            NotationError::InvalidPlayer(p) => write!(f, "invalid player '{}'", p),
+            NotationError::InvalidConstraint(c) => write!(f, "invalid constraint '{}'", c),
+            NotationError::MiniBoardWonByBoth(index) => {
+                write!(f, "mini board {:?} is won by both players", index)
+            }
+            NotationError::MetaBoardWonByBoth => write!(f, "meta board is won by both players"),
+            NotationError::ConstraintToClosedMiniBoard(index) => {
+                write!(f, "constraint points to closed mini board {:?}", index)
+            }
+            NotationError::InitConstraintWithOccupiedCells => {
+                write!(f, "constraint Init requires a board without occupied cells")
+            }
+            NotationError::CurrentPlayerEqualsLastPlayer => {
+                write!(
+                    f,
+                    "current player must differ from last player after first move"
+                )
+            }
+            NotationError::InvalidNumberOfCells { first, second } => write!(
+                f,
+                "number of cells of First ({}) and Second ({}) does not fit to players",
+                first, second
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+pub(crate) fn player_to_notation(player: TicTacToeStatus) -> char {
+    match player {
+        TicTacToeStatus::First => 'X',
+        TicTacToeStatus::Second => 'O',
+        _ => unreachable!("Player is always First or Second"),
+    }
+}
+
+pub(crate) fn player_from_notation(field: &str) -> Result<TicTacToeStatus, NotationError> {
+    match field {
+        "X" => Ok(TicTacToeStatus::First),
+        "O" => Ok(TicTacToeStatus::Second),
+        _ => Err(NotationError::InvalidPlayer(field.to_owned())),
+    }
+}
+
+impl UltTTT {
+    pub fn to_notation(&self) -> String {
+        let mut notation = String::with_capacity(32);
+        for y in 0..9 {
+            if y > 0 {
+                notation.push('/');
+            }
+            let mut vacant = 0;
+            for x in 0..9 {
+                let cell = UltTTTMove::try_from((x, y)).unwrap();
+                match self.get_cell_value(cell) {
+                    TicTacToeStatus::Vacant => vacant += 1,
+                    player => {
+                        if vacant > 0 {
+                            notation.push_str(&vacant.to_string());
+                            vacant = 0;
+                        }
+                        notation.push(player_to_notation(player));
+                    }
+                }
+            }
+            if vacant > 0 {
+                notation.push_str(&vacant.to_string());
+            }
+        }
+        notation.push(' ');
+        notation.push(player_to_notation(self.current_player));
+        notation.push(' ');
+        notation.push(player_to_notation(self.last_player));
+        notation.push(' ');
+        match self.next_action_constraint {
+            NextActionConstraint::Init => notation.push('-'),
+            NextActionConstraint::None => notation.push('*'),
+            NextActionConstraint::MiniBoard(status_index) => {
+                notation.push_str(&usize::from(status_index).to_string())
+            }
+        }
+        notation
+    }
+
+    // notation does not contain rules; positions are parsed with default rules
+    pub fn from_notation(notation: &str) -> Result<UltTTT, NotationError> {
+        UltTTT::from_notation_with_rules(notation, RuleSet::default())
+    }
+
+    // with DecidedBoardPolicy::PlayUntilFull a mini board, which contains lines of both
+    // players, is rejected, since notation does not tell, who won it first
+    pub fn from_notation_with_rules(
+        notation: &str,
+        rules: RuleSet,
+    ) -> Result<UltTTT, NotationError> {
+        let fields: Vec<&str> = notation.split_whitespace().collect();
+        if fields.len() != 4 {
+            return Err(NotationError::WrongNumberOfFields(fields.len()));
+        }
+        let rows: Vec<&str> = fields[0].split('/').collect();
+        if rows.len() != 9 {
+            return Err(NotationError::WrongNumberOfRows(rows.len()));
+        }
+        let mut state = UltTTT::with_rules(rules);
+        for (y, row) in rows.iter().enumerate() {
+            let mut x = 0;
+            for c in row.chars() {
+                let player = match c {
+                    'X' => 0,
+                    'O' => 1,
+                    '1'..='9' => {
+                        x += c.to_digit(10).unwrap() as usize;
+                        continue;
+                    }
+                    _ => return Err(NotationError::InvalidCell(c)),
+                };
+                if x >= 9 {
+                    return Err(NotationError::InvalidRow(y));
+                }
+                let cell = UltTTTMove::try_from((x as u8, y as u8)).unwrap();
+                state.cells[player] |=
+                    1 << (9 * usize::from(cell.status_index) + usize::from(cell.mini_board_index));
+                x += 1;
+            }
+            if x != 9 {
+                return Err(NotationError::InvalidRow(y));
+            }
+        }
+        state.current_player = player_from_notation(fields[1])?;
+        state.last_player = player_from_notation(fields[2])?;
+        state.next_action_constraint = match fields[3] {
+            "-" => NextActionConstraint::Init,
+            "*" => NextActionConstraint::None,
+            field => match field.parse::<usize>() {
+                Ok(index) if index < 9 => NextActionConstraint::MiniBoard(CELL_INDICES[index]),
+                _ => return Err(NotationError::InvalidConstraint(field.to_owned())),
+            },
+        };
+
+        // recompute status map from cells
+        for (status_index, status_cell) in CELL_INDICES.iter().enumerate() {
+            let first = WIN_TABLE[state.get_mini_board_bits(0, status_index) as usize];
+            let second = WIN_TABLE[state.get_mini_board_bits(1, status_index) as usize];
+            let status_bit = 1 << status_index;
+            match (first, second) {
+                (true, true) => return Err(NotationError::MiniBoardWonByBoth(*status_cell)),
+                (true, false) => state.won_boards[0] |= status_bit,
+                (false, true) => state.won_boards[1] |= status_bit,
+                (false, false) => (),
+            }
+            let is_won = first || second;
+            if state.get_vacant_cells(status_index) == 0
+                || (is_won && rules.decided_board_policy == DecidedBoardPolicy::FreeChoice)
+            {
+                state.closed_boards |= status_bit;
+            }
+        }
+        if WIN_TABLE[state.won_boards[0] as usize] && WIN_TABLE[state.won_boards[1] as usize] {
+            return Err(NotationError::MetaBoardWonByBoth);
+        }
+        state.validate_players_and_constraint()?;
+        state.zobrist = state.calc_zobrist();
+        Ok(state)
+    }
+
+    fn validate_players_and_constraint(&self) -> Result<(), NotationError> {
+        let first = self.cells[0].count_ones();
+        let second = self.cells[1].count_ones();
+        match self.next_action_constraint {
+            NextActionConstraint::Init => {
+                if first + second > 0 {
+                    return Err(NotationError::InitConstraintWithOccupiedCells);
+                }
+                return Ok(());
+            }
+            NextActionConstraint::MiniBoard(status_index) => {
+                if self.closed_boards & (1 << usize::from(status_index)) != 0 {
+                    return Err(NotationError::ConstraintToClosedMiniBoard(status_index));
+                }
+            }
+            NextActionConstraint::None => (),
+        }
+        if self.current_player == self.last_player {
+            return Err(NotationError::CurrentPlayerEqualsLastPlayer);
+        }
+        // last player made last move, therefore last player made the same number of moves or
+        // one move more than current player.
+        let (last, current) = match self.last_player {
+            TicTacToeStatus::First => (first, second),
+            _ => (second, first),
+        };
+        if last != current && last != current + 1 {
+            return Err(NotationError::InvalidNumberOfCells { first, second });
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for UltTTT {
+    type Err = NotationError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        UltTTT::from_notation(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UltTTTMCTSGame;
+    use my_lib::my_mcts::{GameCache, MCTSGame, NoGameCache};
+    use rand::prelude::SliceRandom;
+
+    #[test]
+    fn test_initial_notation() {
+        let state = UltTTT::new();
+        assert_eq!(state.to_notation(), "9/9/9/9/9/9/9/9/9 X X -");
+        assert!(UltTTT::from_notation("9/9/9/9/9/9/9/9/9 X X -").unwrap() == state);
+        let mut state = UltTTT::new();
+        state.set_current_player(TicTacToeStatus::Second);
+        assert_eq!(state.to_notation(), "9/9/9/9/9/9/9/9/9 O X -");
+    }
+
+    #[test]
+    fn test_notation_round_trip() {
+        let mut rng = rand::thread_rng();
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        for i in 0..200 {
+            let mut state = UltTTT::new();
+            if i % 2 == 1 {
+                state.set_current_player(TicTacToeStatus::Second);
+            }
+            loop {
+                let notation = state.to_notation();
+                let parsed = UltTTT::from_notation(&notation).unwrap();
+                assert!(parsed == state, "round trip failed for {}", notation);
+                assert_eq!(parsed.zobrist(), state.zobrist());
+                if UltTTTMCTSGame::evaluate(&state, &mut game_cache).is_some() {
+                    break;
+                }
+                let moves: Vec<UltTTTMove> = UltTTTMCTSGame::available_moves(&state).collect();
+                let mv = moves.choose(&mut rng).unwrap();
+                state = UltTTTMCTSGame::apply_move(&state, mv, &mut game_cache);
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_notations() {
+        assert_eq!(
+            UltTTT::from_notation("9/9/9/9/9/9/9/9/9 X X").err(),
+            Some(NotationError::WrongNumberOfFields(3))
+        );
+        assert_eq!(
+            UltTTT::from_notation("9/9/9/9/9/9/9/9 X X -").err(),
+            Some(NotationError::WrongNumberOfRows(8))
+        );
+        assert_eq!(
+            UltTTT::from_notation("8/9/9/9/9/9/9/9/9 X X -").err(),
+            Some(NotationError::InvalidRow(0))
+        );
+        assert_eq!(
+            UltTTT::from_notation("9/9/9/9/4A4/9/9/9/9 X X -").err(),
+            Some(NotationError::InvalidCell('A'))
+        );
+        assert_eq!(
+            UltTTT::from_notation("9/9/9/9/4X4/9/9/9/9 O X -").err(),
+            Some(NotationError::InitConstraintWithOccupiedCells)
+        );
+        assert_eq!(
+            UltTTT::from_notation("9/9/9/9/4X4/9/9/9/9 X X 4").err(),
+            Some(NotationError::CurrentPlayerEqualsLastPlayer)
+        );
+        assert_eq!(
+            UltTTT::from_notation("9/9/9/9/3XX4/9/9/9/9 O X 4").err(),
+            Some(NotationError::InvalidNumberOfCells {
+                first: 2,
+                second: 0
+            })
+        );
+        assert_eq!(
+            UltTTT::from_notation("9/9/9/9/4X4/9/9/9/9 O X 9").err(),
+            Some(NotationError::InvalidConstraint("9".into()))
+        );
+        // mini board TL is won by First
+        assert_eq!(
+            UltTTT::from_notation("XXX6/OO7/9/9/9/9/9/9/9 O X 0").err(),
+            Some(NotationError::ConstraintToClosedMiniBoard(CellIndex3x3::TL))
+        );
+        // mini board TL is won by First and Second
+        assert_eq!(
+            UltTTT::from_notation("XXX6/OOO6/9/9/9/9/9/9/9 X O *").err(),
+            Some(NotationError::MiniBoardWonByBoth(CellIndex3x3::TL))
+        );
+    }
+}
@@ -62,11 +62,11 @@ impl Heuristic<UltTTTMCTSGame> for OldUltTTTHeuristic {
                 // mini board threats, weighted with cell_weight, meta factor and constraint factor
                 let mut my_threat_sum = 0.0;
                 let mut opp_threat_sum = 0.0;
-                for (status_index, _) in state.status_map.iter_map().filter(|(_, c)| c.is_vacant())
-                {
+                let status_map = state.get_status_map();
+                for (status_index, _) in status_map.iter_map().filter(|(_, c)| c.is_vacant()) {
                     // mini board threats
                     let (my_threats, opp_threats) =
-                        game_cache.get_board_threats(state.map.get_cell(status_index));
+                        game_cache.get_board_threats(&state.get_mini_board(status_index));
                     // cell weight
                     let cell_weight = status_index.cell_weight();
                     // meta factors
@@ -75,7 +75,7 @@ impl Heuristic<UltTTTMCTSGame> for OldUltTTTHeuristic {
                         my_meta_small_threats,
                         opp_meta_threats,
                         opp_meta_small_threats,
-                    ) = game_cache.get_meta_cell_threats(&state.status_map, status_index);
+                    ) = game_cache.get_meta_cell_threats(&status_map, status_index);
                     let my_meta_factor = 1.0
                         + heuristic_config.meta_cell_big_threat * my_meta_threats as f32
                         + heuristic_config.meta_cell_small_threat * my_meta_small_threats as f32;
@@ -150,8 +150,7 @@ impl Heuristic<UltTTTMCTSGame> for OldUltTTTHeuristic {
                 }
 
                 // meta progress: wins on status_map
-                let (my_wins, opp_wins, played_cells) =
-                    game_cache.get_board_progress(&state.status_map);
+                let (my_wins, opp_wins, played_cells) = game_cache.get_board_progress(&status_map);
 
                 // calculate heuristic value
                 let progress = played_cells as f32 / 9.0;
@@ -0,0 +1,287 @@
+// opening book of best moves of early plies
+// Positions are stored by their canonical representative (see symmetry), therefore one entry covers
+// all symmetric positions. Key of an entry is upper half of zobrist key of canonical position. Each
+// entry is encoded as 7 characters of a 64 character alphabet (32 bit key and 7 bit cell), which
+// keeps the book small enough to embed it into the single file submission of codingame.
+
+use super::{expected_valid_actions, UltTTT, UltTTTMCTSGame, UltTTTMove, CELL_INDICES};
+use my_lib::my_mcts::{GameCache, MCTSGame, NoGameCache};
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Display;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const CHARS_PER_ENTRY: usize = 7;
+
+// book of opening_book_builder with 3 plies and 120 s of analysis per position
+const EMBEDDED_OPENING_BOOK: &str = "\
+    AIcSmw4AKWtVtKAPLXHY0AUu7ww0AdBBD6mAdGwVWiAfskDUeAmDX+uaAqXb3IMAvgx9JEAwaom60A2emx+a\
+    A8ZfKM4BHkRrGyBKSHGSZBKZZt6sBKr44lQBNXrFUSBOa9q0MBQOMwKABTdbeCMBVEZkKIBYLIr6GBc/x8Mz\
+    BdFlZIOBj0+TU4BpjFTSBBukjh4qByWlOiCB4uvNDEB7XjumoB7b/yKkCCaCrwOCGn8rsoCLG55UOCMhgwrG\
+    CP1StuiCSzmNCLCXEMHDECY460KOCeI9Ky4CiE6i8IClob0wsCpRlW2wCpmXk0ACsKGK6yCwBKSQaC58+t4M\
+    C6/D6WKDGeqKSVDJrrDEIDKyY0oGDNuQ79GDO//a60DUpVP7ODYYca/ODZvhLgTDfrq/wyDgnikXQDiQnayd\
+    DlnvMnLDnauTgYDqdN17EDulQ8s0DvvxsssD00R7moEBcZ10kEBerQjKEDVieJCELaFuzGEa51CcsEbNmv8A\
+    EppsFK8Es1h1CaE3QNCqUE5Usz+UE7WQQuiE8zO2siFHOKAu8FJJXTyaFL2bxgFFN9N2mqFTaFGeuFV1jeCI\
+    FWsRdYAFZbRu5EFZu+pXQFaidNcsFcu0D0cFdjMnu0FgKO+kMFgYKkEiFk7ZdeCFnvJU5QFny8NmOF0nM29E\
+    F1MxAm0F3QGbKkF4E5ZlPF7mZUuqF/UoLU6GDytqkiGFQDHM8GID+UsMGKtojodGPCicCYGURcPIYGfKLzuU\
+    Ggp1rceGidI4WqGif6dBIGpeLFsqGw2NeYMGyi7b0aG2JSe4OG5NEXwMG56WqpKG7aO9y+HBbI3MIHIR/1kq\
+    HNO6PgCHPQufW0HUYv1zLHWluq0YHXhG0IMHc3VBwIHdaZbUKHkM5UIOHkl/bYSHnrUpoVHuUqGkuHxPq4kq\
+    H1crsBOH2mG3VEH+tNmG0H/q36k+";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpeningBookError {
+    // length of encoded book is no multiple of CHARS_PER_ENTRY
+    InvalidLength(usize),
+    InvalidChar(char),
+    // cell of entry is not in 0..81
+    InvalidCell(u8),
+}
+
+impl Display for OpeningBookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpeningBookError::InvalidLength(length) => write!(
+                f,
+                "length {} of opening book is no multiple of {}",
+                length, CHARS_PER_ENTRY
+            ),
+            OpeningBookError::InvalidChar(c) => {
+                write!(f, "{:?} is no character of opening book encoding", c)
+            }
+            OpeningBookError::InvalidCell(cell) => write!(f, "cell {} is out of range", cell),
+        }
+    }
+}
+
+impl std::error::Error for OpeningBookError {}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpeningBook {
+    // (key, cell) sorted by key; cell of move is 9 * status_index + mini_board_index
+    entries: Vec<(u32, u8)>,
+}
+
+impl OpeningBook {
+    pub fn new() -> Self {
+        OpeningBook::default()
+    }
+    // book, which is compiled into bot
+    pub fn embedded() -> Self {
+        OpeningBook::decode(EMBEDDED_OPENING_BOOK).expect("embedded opening book is valid")
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    // stores best move of state; an existing entry of state is replaced
+    pub fn insert(&mut self, state: &UltTTT, best_move: UltTTTMove) {
+        let (canonical, symmetry) = state.canonical();
+        let entry = (
+            book_key(&canonical),
+            move_to_cell(best_move.transform(symmetry)),
+        );
+        match self.entries.binary_search_by_key(&entry.0, |(key, _)| *key) {
+            Ok(index) => self.entries[index] = entry,
+            Err(index) => self.entries.insert(index, entry),
+        }
+    }
+    // returns book move of state or of any symmetric state
+    pub fn lookup(&self, state: &UltTTT) -> Option<UltTTTMove> {
+        let (canonical, symmetry) = state.canonical();
+        let index = self
+            .entries
+            .binary_search_by_key(&book_key(&canonical), |(key, _)| *key)
+            .ok()?;
+        let book_move = cell_to_move(self.entries[index].1).inverse_transform(symmetry);
+        // key is only part of zobrist key: a move of a colliding position is most likely illegal
+        state.check_move(&book_move).ok()?;
+        Some(book_move)
+    }
+    pub fn encode(&self) -> String {
+        let mut encoded = String::with_capacity(self.entries.len() * CHARS_PER_ENTRY);
+        for (key, cell) in self.entries.iter() {
+            let value = (*key as u64) << 7 | *cell as u64;
+            for shift in (0..CHARS_PER_ENTRY).rev() {
+                encoded.push(ALPHABET[(value >> (6 * shift)) as usize & 0x3F] as char);
+            }
+        }
+        encoded
+    }
+    pub fn decode(encoded: &str) -> Result<OpeningBook, OpeningBookError> {
+        let encoded = encoded.trim().as_bytes();
+        if encoded.len() % CHARS_PER_ENTRY != 0 {
+            return Err(OpeningBookError::InvalidLength(encoded.len()));
+        }
+        let mut entries = Vec::with_capacity(encoded.len() / CHARS_PER_ENTRY);
+        for chunk in encoded.chunks(CHARS_PER_ENTRY) {
+            let mut value: u64 = 0;
+            for c in chunk {
+                let digit = ALPHABET
+                    .iter()
+                    .position(|a| a == c)
+                    .ok_or(OpeningBookError::InvalidChar(*c as char))?;
+                value = value << 6 | digit as u64;
+            }
+            let cell = (value & 0x7F) as u8;
+            if cell >= 81 {
+                return Err(OpeningBookError::InvalidCell(cell));
+            }
+            entries.push(((value >> 7) as u32, cell));
+        }
+        entries.sort_unstable_by_key(|(key, _)| *key);
+        entries.dedup_by_key(|(key, _)| *key);
+        Ok(OpeningBook { entries })
+    }
+}
+
+// builds book of all positions up to max_ply, in which own bot is to move, if it follows the book:
+// as start player from initial position and as second player after every first move of opponent.
+// analyze() returns best move of a canonical position.
+pub fn build_opening_book<F>(max_ply: usize, mut analyze: F) -> OpeningBook
+where
+    F: FnMut(&UltTTT) -> UltTTTMove,
+{
+    let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+    let mut book = OpeningBook::new();
+    let mut seen: HashSet<u64> = HashSet::new();
+    let mut queue: VecDeque<(UltTTT, usize)> = VecDeque::new();
+    let initial_state = UltTTT::new();
+    queue.push_back((initial_state, 0));
+    push_replies(&initial_state, 1, max_ply, &mut seen, &mut queue);
+    // breadth first: positions are analyzed ply by ply
+    while let Some((state, ply)) = queue.pop_front() {
+        let best_move = analyze(&state);
+        book.insert(&state, best_move);
+        let next_state = UltTTTMCTSGame::apply_move(&state, &best_move, &mut game_cache);
+        push_replies(&next_state, ply + 2, max_ply, &mut seen, &mut queue);
+    }
+    book
+}
+
+// queues canonical positions after every reply of opponent, which are not yet seen
+fn push_replies(
+    state: &UltTTT,
+    ply: usize,
+    max_ply: usize,
+    seen: &mut HashSet<u64>,
+    queue: &mut VecDeque<(UltTTT, usize)>,
+) {
+    if ply > max_ply || state.get_status() != TicTacToeStatus::Vacant {
+        return;
+    }
+    let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+    // codingame allows opponent to start in every cell
+    for reply in expected_valid_actions(state) {
+        let next_state = UltTTTMCTSGame::apply_move(state, &reply, &mut game_cache);
+        if next_state.get_status() != TicTacToeStatus::Vacant {
+            continue;
+        }
+        let (canonical, _) = next_state.canonical();
+        if seen.insert(canonical.zobrist()) {
+            queue.push_back((canonical, ply));
+        }
+    }
+}
+
+fn book_key(canonical: &UltTTT) -> u32 {
+    (canonical.zobrist() >> 32) as u32
+}
+
+fn move_to_cell(mv: UltTTTMove) -> u8 {
+    (9 * usize::from(mv.status_index) + usize::from(mv.mini_board_index)) as u8
+}
+
+fn cell_to_move(cell: u8) -> UltTTTMove {
+    UltTTTMove {
+        status_index: CELL_INDICES[cell as usize / 9],
+        mini_board_index: CELL_INDICES[cell as usize % 9],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Symmetry;
+    use my_lib::my_map_3x3::CellIndex3x3;
+
+    // cheap analysis: first available move
+    fn first_move(state: &UltTTT) -> UltTTTMove {
+        UltTTTMCTSGame::available_moves(state).next().unwrap()
+    }
+
+    #[test]
+    fn test_build_and_lookup_symmetric_positions() {
+        let book = build_opening_book(3, first_move);
+        assert!(!book.is_empty());
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        let state = UltTTT::new();
+        let book_move = book.lookup(&state).unwrap();
+        assert_eq!(book_move, first_move(&state));
+        // as second player every first move of opponent is in book
+        for reply in expected_valid_actions(&state) {
+            let next_state = UltTTTMCTSGame::apply_move(&state, &reply, &mut game_cache);
+            for symmetry in Symmetry::ALL {
+                let transformed = next_state.transform(symmetry);
+                let book_move = book.lookup(&transformed).unwrap();
+                assert!(transformed.check_move(&book_move).is_ok());
+                // book move of symmetric state is symmetric to book move of state; positions with
+                // symmetries of their own may get another, but equivalent move
+                let after_book_move =
+                    UltTTTMCTSGame::apply_move(&transformed, &book_move, &mut game_cache);
+                let expected = UltTTTMCTSGame::apply_move(
+                    &next_state,
+                    &book.lookup(&next_state).unwrap(),
+                    &mut game_cache,
+                );
+                assert!(after_book_move.canonical().0 == expected.canonical().0);
+            }
+        }
+        // positions, which are not reached by following the book, are not in book
+        let off_book_move = UltTTTMove {
+            status_index: CellIndex3x3::MM,
+            mini_board_index: CellIndex3x3::MM,
+        };
+        assert_ne!(off_book_move, book_move);
+        let state = UltTTTMCTSGame::apply_move(&state, &off_book_move, &mut game_cache);
+        let reply = first_move(&state);
+        let state = UltTTTMCTSGame::apply_move(&state, &reply, &mut game_cache);
+        assert!(book.lookup(&state).is_none());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let book = build_opening_book(2, first_move);
+        let encoded = book.encode();
+        assert_eq!(encoded.len(), book.len() * CHARS_PER_ENTRY);
+        assert_eq!(OpeningBook::decode(&encoded).unwrap(), book);
+        assert_eq!(
+            OpeningBook::decode("ABC"),
+            Err(OpeningBookError::InvalidLength(3))
+        );
+        assert_eq!(
+            OpeningBook::decode("ABCDEF!"),
+            Err(OpeningBookError::InvalidChar('!'))
+        );
+        // cell 127
+        assert_eq!(
+            OpeningBook::decode("AAAAAB/"),
+            Err(OpeningBookError::InvalidCell(127))
+        );
+    }
+
+    #[test]
+    fn test_embedded_book_covers_book_lines() {
+        let embedded = OpeningBook::embedded();
+        assert_eq!(embedded.len(), 136);
+        // every position reached by following embedded book has a legal book move
+        let rebuild = build_opening_book(3, |state| {
+            embedded
+                .lookup(state)
+                .expect("position of book line is in book")
+        });
+        assert_eq!(rebuild, embedded);
+    }
+}
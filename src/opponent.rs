@@ -0,0 +1,463 @@
+// opponents of matches and tournaments: search engines of this crate and simple baselines
+// Each opponent creates an engine behind the object safe MatchEngine interface, so that engines of
+// different types can play each other. Baselines do not search; they pick their move on demand.
+// Random numbers of engines are drawn with with_match_rng() (see match_rng), except for GenV00:
+// its engine is the training engine of ml_linfa, whose simulation draws playout moves from
+// thread_rng(). Matches against GenV00 do not replay (see is_reproducible()).
+
+use super::old_heuristic::OldUltTTTHeuristic;
+use super::utilities::{Config, UltTTTMCTSFirst, UltTTTMCTSSecond};
+use super::{
+    config_record, with_match_rng, ConfigRecord, DecidedOutcomeCutoff, MatchRngExpandAll,
+    MatchRngPlayout, RootLeaders, RootStatistics, UltTTT, UltTTTHeuristic, UltTTTHeuristicConfig,
+    UltTTTMCTSConfig, UltTTTMCTSGame, UltTTTMove, UltTTTZobristTT,
+};
+use my_lib::my_mcts::{
+    CachedUTC, DefaultSimulationPolicy, DynamicC, ExpansionPolicy, GameCache, Heuristic,
+    HeuristicCache, HeuristicProgressiveWidening, MCTSAlgo, MCTSConfig, MCTSGame, NoGameCache,
+    NoHeuristic, NoHeuristicCache, PlainMCTS, SimulationPolicy, TranspositionTable, UCTPolicy,
+    UTCCache,
+};
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+use rand::prelude::SliceRandom;
+
+use std::any::Any;
+
+const EXPECTED_NUM_NODES: usize = 200_000;
+
+type OldHeuristicMCTS = PlainMCTS<
+    UltTTTMCTSGame,
+    OldUltTTTHeuristic,
+    UltTTTMCTSConfig,
+    CachedUTC,
+    UltTTTZobristTT,
+    DynamicC,
+    HeuristicProgressiveWidening<UltTTTMCTSGame, OldUltTTTHeuristic, UltTTTMCTSConfig>,
+    MatchRngPlayout<DecidedOutcomeCutoff>,
+>;
+type PureMCTS = PlainMCTS<
+    UltTTTMCTSGame,
+    NoHeuristic,
+    UltTTTMCTSConfig,
+    CachedUTC,
+    UltTTTZobristTT,
+    DynamicC,
+    MatchRngExpandAll,
+    MatchRngPlayout<DefaultSimulationPolicy>,
+>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Opponent {
+    // current heuristic without exploration boost (DynamicC)
+    Heuristic(Config),
+    // current heuristic with exploration boost of config (DynamicCWithExplorationBoost), e.g. a
+    // candidate of optimization
+    HeuristicWithExplorationBoost(Config),
+    // heuristic, which was replaced by UltTTTHeuristic
+    OldHeuristic(Config),
+    // random playouts without heuristic
+    PureMCTS(UltTTTMCTSConfig),
+    // generation 0 of learned heuristics (see ml_linfa)
+    GenV00,
+    // uniformly random legal move
+    Random,
+    // legal move with best heuristic value after one ply
+    Greedy(UltTTTHeuristicConfig),
+}
+
+// sparring partner of optimization before opponents were configurable
+impl Default for Opponent {
+    fn default() -> Self {
+        Opponent::Heuristic(Config {
+            mcts: UltTTTMCTSConfig::new_optimized(),
+            heuristic: UltTTTHeuristicConfig::new_optimized(),
+        })
+    }
+}
+
+impl Opponent {
+    pub const PRESETS: [&'static str; 9] = [
+        "default",
+        "optimized",
+        "new_optimized",
+        "optimized_v05",
+        "old_heuristic",
+        "pure_mcts",
+        "gen_v00",
+        "random",
+        "greedy",
+    ];
+    pub fn preset(name: &str) -> Option<Self> {
+        let opponent = match name {
+            "default" => Opponent::Heuristic(Config::default()),
+            "optimized" => Opponent::Heuristic(Config {
+                mcts: UltTTTMCTSConfig::optimized(),
+                heuristic: UltTTTHeuristicConfig::optimized(),
+            }),
+            "new_optimized" => Opponent::default(),
+            "optimized_v05" => Opponent::Heuristic(Config {
+                mcts: UltTTTMCTSConfig::optimized_v05(),
+                heuristic: UltTTTHeuristicConfig::optimized_v05(),
+            }),
+            "old_heuristic" => Opponent::OldHeuristic(Config {
+                mcts: UltTTTMCTSConfig::optimized(),
+                heuristic: UltTTTHeuristicConfig::optimized(),
+            }),
+            "pure_mcts" => Opponent::PureMCTS(UltTTTMCTSConfig::default()),
+            "gen_v00" => Opponent::GenV00,
+            "random" => Opponent::Random,
+            "greedy" => Opponent::Greedy(UltTTTHeuristicConfig::new_optimized()),
+            _ => return None,
+        };
+        Some(opponent)
+    }
+    pub fn config_record(&self) -> ConfigRecord {
+        match self {
+            Opponent::Heuristic(config)
+            | Opponent::HeuristicWithExplorationBoost(config)
+            | Opponent::OldHeuristic(config) => config.parameter_map(),
+            Opponent::PureMCTS(mcts_config) => config_record(mcts_config, &NoHeuristic {}),
+            Opponent::GenV00 => config_record(&UltTTTMCTSConfig::config_gen_v00(), &NoHeuristic {}),
+            Opponent::Random => ConfigRecord::new(),
+            Opponent::Greedy(heuristic_config) => {
+                config_record(&UltTTTMCTSConfig::default(), heuristic_config)
+            }
+        }
+    }
+    // a match with deterministic budget and seed replays the same game
+    pub fn is_reproducible(&self) -> bool {
+        !matches!(self, Opponent::GenV00)
+    }
+    // engine, which plays as player; only HeuristicWithExplorationBoost uses exploration boost,
+    // which is defined from perspective of First and is swapped for Second
+    pub fn engine(&self, player: TicTacToeStatus) -> Box<dyn MatchEngine> {
+        match self {
+            Opponent::Heuristic(config) => {
+                let mcts: UltTTTMCTSSecond =
+                    PlainMCTS::new(config.mcts.clone(), config.heuristic, EXPECTED_NUM_NODES);
+                Box::new(mcts)
+            }
+            Opponent::HeuristicWithExplorationBoost(config) => {
+                let mcts: UltTTTMCTSFirst = PlainMCTS::new(
+                    exploration_boost_of(&config.mcts, player),
+                    config.heuristic,
+                    EXPECTED_NUM_NODES,
+                );
+                Box::new(mcts)
+            }
+            Opponent::OldHeuristic(config) => {
+                let mcts: OldHeuristicMCTS =
+                    PlainMCTS::new(config.mcts.clone(), config.heuristic, EXPECTED_NUM_NODES);
+                Box::new(mcts)
+            }
+            Opponent::PureMCTS(mcts_config) => {
+                let mcts: PureMCTS =
+                    PlainMCTS::new(mcts_config.clone(), NoHeuristic {}, EXPECTED_NUM_NODES);
+                Box::new(mcts)
+            }
+            Opponent::GenV00 => Box::new(super::ml_linfa::MCTSGenV00::new(
+                UltTTTMCTSConfig::config_gen_v00(),
+                NoHeuristic {},
+                EXPECTED_NUM_NODES,
+            )),
+            Opponent::Random => Box::new(RandomEngine {
+                state: UltTTT::new(),
+            }),
+            Opponent::Greedy(heuristic_config) => Box::new(GreedyEngine {
+                state: UltTTT::new(),
+                heuristic_config: *heuristic_config,
+            }),
+        }
+    }
+}
+
+fn exploration_boost_of(
+    mcts_config: &UltTTTMCTSConfig,
+    player: TicTacToeStatus,
+) -> UltTTTMCTSConfig {
+    let mut mcts_config = mcts_config.clone();
+    if player == TicTacToeStatus::Second {
+        mcts_config.base_config.exploration_boost = [
+            (
+                TicTacToeStatus::First,
+                mcts_config.exploration_boost(TicTacToeStatus::Second),
+            ),
+            (
+                TicTacToeStatus::Second,
+                mcts_config.exploration_boost(TicTacToeStatus::First),
+            ),
+        ]
+        .into();
+    }
+    mcts_config
+}
+
+// weighted pool of opponents, e.g. for UltTTTObjectiveFunction
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpponentPool {
+    opponents: Vec<(Opponent, f64)>,
+}
+
+impl Default for OpponentPool {
+    fn default() -> Self {
+        OpponentPool::new(Opponent::default())
+    }
+}
+
+impl OpponentPool {
+    pub fn new(opponent: Opponent) -> Self {
+        OpponentPool {
+            opponents: vec![(opponent, 1.0)],
+        }
+    }
+    // weight is relative to weights of other opponents of pool
+    pub fn with_opponent(mut self, opponent: Opponent, weight: f64) -> Self {
+        assert!(weight > 0.0, "weight of opponent must be positive");
+        self.opponents.push((opponent, weight));
+        self
+    }
+    pub fn opponents(&self) -> &[(Opponent, f64)] {
+        &self.opponents
+    }
+    // opponent of each match: both matches of a pair (2 * k and 2 * k + 1) have the same opponent,
+    // since start player alternates. Pairs are distributed by smooth weighted round robin, so that
+    // shares of opponents are close to their weights already after few matches.
+    pub fn schedule(&self, num_matches: usize) -> Vec<&Opponent> {
+        let total_weight: f64 = self.opponents.iter().map(|(_, weight)| weight).sum();
+        let mut current = vec![0.0; self.opponents.len()];
+        let mut schedule = Vec::with_capacity(num_matches);
+        while schedule.len() < num_matches {
+            for (current, (_, weight)) in current.iter_mut().zip(self.opponents.iter()) {
+                *current += weight;
+            }
+            let selected = (0..current.len())
+                .max_by(|a, b| current[*a].total_cmp(&current[*b]).then(b.cmp(a)))
+                .expect("pool contains at least one opponent");
+            current[selected] -= total_weight;
+            let opponent = &self.opponents[selected].0;
+            schedule.push(opponent);
+            if schedule.len() < num_matches {
+                schedule.push(opponent);
+            }
+        }
+        schedule
+    }
+}
+
+// object safe search interface, so that engines of different types can play each other
+// Methods are named differently from MCTSAlgo, so that both traits can be in scope.
+pub trait MatchEngine {
+    fn update_root(&mut self, state: &UltTTT) -> bool;
+    fn search_step(&mut self);
+    fn best_move(&self) -> UltTTTMove;
+    fn leaders(&self) -> Option<RootLeaders>;
+    fn num_nodes(&self) -> usize;
+    // e.g. to analyze tree of an engine of known type
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<H, MC, UC, TT, UP, EP, SP> MatchEngine for PlainMCTS<UltTTTMCTSGame, H, MC, UC, TT, UP, EP, SP>
+where
+    H: Heuristic<UltTTTMCTSGame> + 'static,
+    MC: MCTSConfig<TicTacToeStatus> + 'static,
+    UC: UTCCache<UltTTTMCTSGame, UP, MC> + 'static,
+    TT: TranspositionTable<UltTTT, usize> + 'static,
+    UP: UCTPolicy<UltTTTMCTSGame, MC> + 'static,
+    EP: ExpansionPolicy<UltTTTMCTSGame, H, MC> + 'static,
+    SP: SimulationPolicy<UltTTTMCTSGame, H, MC> + 'static,
+{
+    fn update_root(&mut self, state: &UltTTT) -> bool {
+        MCTSAlgo::set_root(self, state)
+    }
+    fn search_step(&mut self) {
+        MCTSAlgo::iterate(self)
+    }
+    fn best_move(&self) -> UltTTTMove {
+        *MCTSAlgo::select_move(self)
+    }
+    fn leaders(&self) -> Option<RootLeaders> {
+        RootStatistics::root_leaders(self)
+    }
+    fn num_nodes(&self) -> usize {
+        self.tree.nodes.len()
+    }
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+struct RandomEngine {
+    state: UltTTT,
+}
+
+impl MatchEngine for RandomEngine {
+    fn update_root(&mut self, state: &UltTTT) -> bool {
+        self.state = *state;
+        false
+    }
+    fn search_step(&mut self) {}
+    fn best_move(&self) -> UltTTTMove {
+        let moves: Vec<UltTTTMove> = UltTTTMCTSGame::available_moves(&self.state).collect();
+        *with_match_rng(|rng| moves.choose(rng)).expect("state is not terminal")
+    }
+    fn leaders(&self) -> Option<RootLeaders> {
+        None
+    }
+    fn num_nodes(&self) -> usize {
+        0
+    }
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+struct GreedyEngine {
+    state: UltTTT,
+    heuristic_config: UltTTTHeuristicConfig,
+}
+
+impl MatchEngine for GreedyEngine {
+    fn update_root(&mut self, state: &UltTTT) -> bool {
+        self.state = *state;
+        false
+    }
+    fn search_step(&mut self) {}
+    fn best_move(&self) -> UltTTTMove {
+        // sort_moves() shuffles moves of equal value
+        let moves: Vec<UltTTTMove> = UltTTTMCTSGame::available_moves(&self.state).collect();
+        UltTTTHeuristic::sort_moves(
+            &self.state,
+            moves,
+            &mut NoGameCache::new(),
+            &mut NoHeuristicCache::new(),
+            &self.heuristic_config,
+        )
+        .first()
+        .expect("state is not terminal")
+        .1
+    }
+    fn leaders(&self) -> Option<RootLeaders> {
+        None
+    }
+    fn num_nodes(&self) -> usize {
+        0
+    }
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::{run_match, MatchBudget};
+    use crate::{ChannelGameRecordSink, GameRecord};
+
+    #[test]
+    fn test_schedule_follows_weights() {
+        let pool = OpponentPool::new(Opponent::Random).with_opponent(Opponent::GenV00, 2.0);
+        let schedule = pool.schedule(13);
+        assert_eq!(schedule.len(), 13);
+        // both matches of a pair have the same opponent
+        for pair in schedule.chunks_exact(2) {
+            assert_eq!(pair[0], pair[1]);
+        }
+        let random = schedule
+            .iter()
+            .filter(|opponent| ***opponent == Opponent::Random)
+            .count();
+        assert_eq!(random, 4);
+        assert_eq!(
+            OpponentPool::default().schedule(3),
+            vec![&Opponent::default(); 3]
+        );
+    }
+
+    #[test]
+    fn test_engine_types_of_heuristic_opponents() {
+        // sparring partner of optimization is DynamicC without exploration boost
+        assert!(Opponent::default()
+            .engine(TicTacToeStatus::Second)
+            .into_any()
+            .downcast::<UltTTTMCTSSecond>()
+            .is_ok());
+        let mut config = Config::default();
+        config.mcts.base_config.exploration_boost = [
+            (TicTacToeStatus::First, 1.5),
+            (TicTacToeStatus::Second, 0.5),
+        ]
+        .into();
+        let opponent = Opponent::HeuristicWithExplorationBoost(config);
+        for (player, own_boost) in [
+            (TicTacToeStatus::First, 1.5),
+            (TicTacToeStatus::Second, 0.5),
+        ] {
+            let engine = opponent
+                .engine(player)
+                .into_any()
+                .downcast::<UltTTTMCTSFirst>()
+                .ok()
+                .unwrap();
+            assert_eq!(
+                engine.mcts_config.exploration_boost(TicTacToeStatus::First),
+                own_boost
+            );
+        }
+    }
+
+    #[test]
+    fn test_seeded_match_against_pure_mcts_replays() {
+        let opponent = Opponent::preset("pure_mcts").unwrap();
+        assert!(opponent.is_reproducible());
+        assert!(!Opponent::GenV00.is_reproducible());
+        let play = |seed: u64| {
+            let (tx, rx) = crossbeam::channel::unbounded();
+            run_match(
+                Config::default(),
+                &opponent,
+                false,
+                &MatchBudget::Iterations(30),
+                seed,
+                ChannelGameRecordSink::new(tx),
+            );
+            let record: GameRecord = rx.recv().unwrap();
+            record
+                .moves
+                .iter()
+                .map(|mv| (mv.row, mv.col))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(play(5), play(5));
+        assert_ne!(play(5), play(6));
+    }
+
+    #[test]
+    fn test_greedy_wins_against_random() {
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        let greedy = Opponent::preset("greedy").unwrap();
+        let mut score = 0.0;
+        for game in 0..4 {
+            let _match_rng = crate::seed_match_rng(game);
+            let mut engines = [
+                greedy.engine(TicTacToeStatus::First),
+                Opponent::Random.engine(TicTacToeStatus::Second),
+            ];
+            let mut state = UltTTT::new();
+            if game % 2 == 1 {
+                state.set_current_player(TicTacToeStatus::Second);
+            }
+            while UltTTTMCTSGame::evaluate(&state, &mut game_cache).is_none() {
+                let engine = match UltTTTMCTSGame::current_player(&state) {
+                    TicTacToeStatus::First => &mut engines[0],
+                    _ => &mut engines[1],
+                };
+                engine.update_root(&state);
+                let mv = engine.best_move();
+                assert!(UltTTTMCTSGame::available_moves(&state).any(|legal| legal == mv));
+                state = UltTTTMCTSGame::apply_move(&state, &mv, &mut game_cache);
+            }
+            score += UltTTTMCTSGame::evaluate(&state, &mut game_cache).unwrap();
+        }
+        assert!(score >= 3.0, "greedy scored {} of 4", score);
+    }
+}
@@ -0,0 +1,139 @@
+// static detection of positions, whose result is already fixed
+
+use super::{BitIter, TieBreak, UltTTT, UltTTTMCTSGame, LINE_MASKS, MINI_BOARD_MASK, WIN_TABLE};
+use my_lib::my_mcts::{GameCache, MCTSGame, NoGameCache};
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+
+use std::cmp::Ordering;
+
+impl UltTTT {
+    // returns result (First, Second or Tie), if it cannot be changed by any sequence of moves.
+    // Analysis is conservative: None does not mean, that result is still open.
+    pub fn decided_outcome(&self) -> Option<TicTacToeStatus> {
+        let mut game_cache: NoGameCache<UltTTT, _> = NoGameCache::new();
+        if let Some(score) = UltTTTMCTSGame::evaluate(self, &mut game_cache) {
+            return Some(match score {
+                s if s > 0.5 => TicTacToeStatus::First,
+                s if s < 0.5 => TicTacToeStatus::Second,
+                _ => TicTacToeStatus::Tie,
+            });
+        }
+        // mini boards, which may still be won by First or Second
+        let undecided_boards =
+            !(self.won_boards[0] | self.won_boards[1] | self.closed_boards) & MINI_BOARD_MASK;
+        let winnable_boards = [
+            self.get_winnable_boards(0, undecided_boards),
+            self.get_winnable_boards(1, undecided_boards),
+        ];
+        if self.has_open_meta_line(0, undecided_boards, winnable_boards[0])
+            || self.has_open_meta_line(1, undecided_boards, winnable_boards[1])
+        {
+            return None;
+        }
+        // nobody can win meta board: game ends after all mini boards are closed
+        if self.rules.tie_break == TieBreak::Draw {
+            return Some(TicTacToeStatus::Tie);
+        }
+        let first_won = self.won_boards[0].count_ones();
+        let second_won = self.won_boards[1].count_ones();
+        let first_winnable = winnable_boards[0].count_ones();
+        let second_winnable = winnable_boards[1].count_ones();
+        if first_won > second_won + second_winnable {
+            return Some(TicTacToeStatus::First);
+        }
+        if second_won > first_won + first_winnable {
+            return Some(TicTacToeStatus::Second);
+        }
+        if first_winnable + second_winnable == 0 {
+            // all undecided mini boards are dead and will end tied
+            return Some(match first_won.cmp(&second_won) {
+                Ordering::Greater => TicTacToeStatus::First,
+                Ordering::Less => TicTacToeStatus::Second,
+                Ordering::Equal => TicTacToeStatus::Tie,
+            });
+        }
+        None
+    }
+    // undecided mini boards with at least one line without cells of opponent
+    fn get_winnable_boards(&self, player: usize, undecided_boards: u16) -> u16 {
+        BitIter(undecided_boards)
+            .filter(|&status_index| {
+                let opponent_bits = self.get_mini_board_bits(1 - player, status_index);
+                LINE_MASKS.iter().any(|&line| line & opponent_bits == 0)
+            })
+            .fold(0, |boards, status_index| boards | 1 << status_index)
+    }
+    fn has_open_meta_line(&self, player: usize, undecided_boards: u16, winnable: u16) -> bool {
+        let own_boards = self.won_boards[player] | winnable;
+        if !self.rules.tied_boards_count_for_both {
+            return WIN_TABLE[own_boards as usize];
+        }
+        // every undecided mini board may still end tied; mini boards won by opponent keep their
+        // owner with every DecidedBoardPolicy
+        let counting_boards =
+            (own_boards | self.get_tied_boards() | undecided_boards) & !self.won_boards[1 - player];
+        LINE_MASKS
+            .iter()
+            .any(|&line| counting_boards & line == line && own_boards & line != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EndgameSolver, GameValue, RuleSet, UltTTTMove};
+    use rand::prelude::SliceRandom;
+
+    #[test]
+    fn test_dead_mini_board_and_majority() {
+        let mut state = UltTTT::with_rules(RuleSet::CODINGAME);
+        // First: TL, TM, MR, BL; Second: TR, ML, BR; tied: BM; open: MM
+        state.won_boards = [0b0_0110_0011, 0b1_0000_1100];
+        state.closed_boards = 0b1_1110_1111;
+        // First blocks all lines of MM for Second without owning a line of MM
+        for mini_board_index in [1, 3, 4, 8] {
+            state.cells[0] |= 1 << (9 * 4 + mini_board_index);
+        }
+        assert_eq!(state.decided_outcome(), Some(TicTacToeStatus::First));
+        state.rules = RuleSet::CLASSIC;
+        assert_eq!(state.decided_outcome(), Some(TicTacToeStatus::Tie));
+        // Second may still win MM: no majority for First
+        state.rules = RuleSet::CODINGAME;
+        state.cells[0] &= !(1 << (9 * 4 + 8));
+        assert_eq!(state.decided_outcome(), None);
+    }
+
+    #[test]
+    fn test_decided_outcome_matches_solver() {
+        let mut rng = rand::thread_rng();
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        let mut solver = EndgameSolver::new(16);
+        let mut num_decided = 0;
+        for i in 0..300 {
+            let rules = match i % 3 {
+                0 => RuleSet::CODINGAME,
+                1 => RuleSet::CLASSIC,
+                _ => RuleSet::TIED_BOARDS_COUNT_FOR_BOTH,
+            };
+            let mut state = UltTTT::with_rules(rules);
+            while UltTTTMCTSGame::evaluate(&state, &mut game_cache).is_none() {
+                if state.count_playable_cells() <= 16 {
+                    if let Some(outcome) = state.decided_outcome() {
+                        num_decided += 1;
+                        let value = solver.solve_exact(&state).unwrap().value;
+                        let expected = match (outcome, state.current_player) {
+                            (TicTacToeStatus::Tie, _) => GameValue::Draw,
+                            (winner, current) if winner == current => GameValue::Win,
+                            _ => GameValue::Loss,
+                        };
+                        assert_eq!(value, expected, "{}", state.to_notation());
+                    }
+                }
+                let moves: Vec<UltTTTMove> = state.legal_moves().into_iter().collect();
+                let mv = moves.choose(&mut rng).unwrap();
+                state = UltTTTMCTSGame::apply_move(&state, mv, &mut game_cache);
+            }
+        }
+        assert!(num_decided > 0);
+    }
+}
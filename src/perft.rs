@@ -0,0 +1,108 @@
+// perft: number of move sequences of given depth
+// Counts pin down behavior of available_moves() and apply_move(), including detection of terminal
+// states. Terminal states have no successors, even if they still have vacant cells.
+
+use super::{UltTTT, UltTTTMCTSGame, UltTTTMove};
+use my_lib::my_mcts::{GameCache, MCTSGame, NoGameCache};
+
+pub fn perft(state: &UltTTT, depth: usize) -> u64 {
+    let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+    perft_recursive(state, depth, &mut game_cache)
+}
+
+// perft count of each available move of state
+pub fn perft_divide(state: &UltTTT, depth: usize) -> Vec<(UltTTTMove, u64)> {
+    let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+    if depth == 0 || UltTTTMCTSGame::evaluate(state, &mut game_cache).is_some() {
+        return Vec::new();
+    }
+    UltTTTMCTSGame::available_moves(state)
+        .map(|mv| {
+            let next_state = UltTTTMCTSGame::apply_move(state, &mv, &mut game_cache);
+            (mv, perft_recursive(&next_state, depth - 1, &mut game_cache))
+        })
+        .collect()
+}
+
+fn perft_recursive(
+    state: &UltTTT,
+    depth: usize,
+    game_cache: &mut NoGameCache<UltTTT, UltTTTMove>,
+) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    if UltTTTMCTSGame::evaluate(state, game_cache).is_some() {
+        return 0;
+    }
+    if depth == 1 {
+        // bulk counting: moves of last ply are not applied
+        return UltTTTMCTSGame::available_moves(state).count() as u64;
+    }
+    UltTTTMCTSGame::available_moves(state)
+        .map(|mv| {
+            let next_state = UltTTTMCTSGame::apply_move(state, &mv, game_cache);
+            perft_recursive(&next_state, depth - 1, game_cache)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RuleSet;
+
+    // late position with terminal states in search depth
+    const LATE_POSITION: &str =
+        "OOXX2O2/XXOX2OOX/OXOO2XO1/X2X1OO2/1XOXO1OXX/O1OO1O1XO/O1XXOOXXX/1X2X1O1X/1XX1O4 X O 1";
+
+    #[test]
+    fn test_perft_initial_position() {
+        // legal_moves() restricts first move to mini board MM
+        let expected = [1, 9, 80, 704, 6_120, 52_584, 446_944];
+        let state = UltTTT::new();
+        for (depth, count) in expected.iter().enumerate() {
+            assert_eq!(perft(&state, depth), *count, "depth {}", depth);
+        }
+    }
+
+    #[test]
+    fn test_perft_free_choice_position() {
+        // mini board TL is won by First and Second has free choice
+        let expected = [1, 70, 1_090, 16_342, 236_258];
+        let state = UltTTT::from_notation("X8/1X7/2X6/9/4O4/9/9/9/8O O X *").unwrap();
+        for (depth, count) in expected.iter().enumerate() {
+            assert_eq!(perft(&state, depth), *count, "depth {}", depth);
+        }
+    }
+
+    #[test]
+    fn test_perft_late_position_with_rule_sets() {
+        let expected = [1, 4, 16, 132, 991, 6_497, 44_349, 270_518];
+        // tied boards counting for both end some games earlier
+        let expected_tied_count = [1, 4, 16, 132, 991, 6_497, 43_701, 261_559];
+        for (rules, expected) in [
+            (RuleSet::CODINGAME, expected),
+            (RuleSet::CLASSIC, expected),
+            (RuleSet::TIED_BOARDS_COUNT_FOR_BOTH, expected_tied_count),
+        ] {
+            let state = UltTTT::from_notation_with_rules(LATE_POSITION, rules).unwrap();
+            for (depth, count) in expected.iter().enumerate() {
+                assert_eq!(perft(&state, depth), *count, "{:?} depth {}", rules, depth);
+            }
+        }
+    }
+
+    #[test]
+    fn test_perft_divide_sums_to_perft() {
+        let state = UltTTT::from_notation(LATE_POSITION).unwrap();
+        let divide = perft_divide(&state, 5);
+        assert_eq!(divide.len(), 4);
+        assert_eq!(divide.iter().map(|(_, count)| count).sum::<u64>(), 6_497);
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        for (mv, count) in divide {
+            let next_state = UltTTTMCTSGame::apply_move(&state, &mv, &mut game_cache);
+            assert_eq!(perft(&next_state, 4), count);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// heuristic progressive widening, which uses legal_move_count() of UltTTT
+// HeuristicProgressiveWidening generates and sorts all moves of each new node by heuristic value.
+// If there is only one legal move, its heuristic value has no effect, since the first expansion of
+// a node always expands at least one move. Therefore a forced move is taken directly from the
+// count without heuristic evaluation; all other nodes are delegated to
+// HeuristicProgressiveWidening.
+
+use super::{UltTTT, UltTTTMCTSGame, UltTTTMove};
+use my_lib::my_mcts::{
+    ExpansionPolicy, Heuristic, HeuristicProgressiveWidening, MCTSConfig, MCTSGame,
+};
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+
+#[derive(Clone)]
+pub struct UltTTTProgressiveWidening<H, Config>
+where
+    H: Heuristic<UltTTTMCTSGame>,
+    Config: MCTSConfig<TicTacToeStatus>,
+{
+    pub widening: HeuristicProgressiveWidening<UltTTTMCTSGame, H, Config>,
+}
+
+impl<H, Config> ExpansionPolicy<UltTTTMCTSGame, H, Config> for UltTTTProgressiveWidening<H, Config>
+where
+    H: Heuristic<UltTTTMCTSGame>,
+    Config: MCTSConfig<TicTacToeStatus>,
+{
+    fn new(
+        state: &UltTTT,
+        game_cache: &mut <UltTTTMCTSGame as MCTSGame>::Cache,
+        heuristic_cache: &mut H::Cache,
+        heuristic_config: &H::Config,
+    ) -> Self {
+        let widening = if state.legal_move_count() == 1
+            && UltTTTMCTSGame::evaluate(state, game_cache).is_none()
+        {
+            // heuristic value of forced move is never compared with threshold
+            HeuristicProgressiveWidening {
+                unexpanded_moves: state
+                    .legal_moves()
+                    .into_iter()
+                    .map(|mv| (1.0, mv))
+                    .collect(),
+                phantom: std::marker::PhantomData,
+            }
+        } else {
+            HeuristicProgressiveWidening::new(state, game_cache, heuristic_cache, heuristic_config)
+        };
+        UltTTTProgressiveWidening { widening }
+    }
+    fn should_expand(
+        &self,
+        visits: usize,
+        num_parent_children: usize,
+        mcts_config: &Config,
+        heuristic_config: &H::Config,
+    ) -> bool {
+        self.widening
+            .should_expand(visits, num_parent_children, mcts_config, heuristic_config)
+    }
+    fn expandable_moves(
+        &mut self,
+        visits: usize,
+        num_parent_children: usize,
+        state: &UltTTT,
+        mcts_config: &Config,
+        heuristic_config: &H::Config,
+    ) -> Vec<UltTTTMove> {
+        self.widening.expandable_moves(
+            visits,
+            num_parent_children,
+            state,
+            mcts_config,
+            heuristic_config,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{UltTTTHeuristic, UltTTTHeuristicConfig, UltTTTMCTSConfig};
+    use my_lib::my_mcts::{GameCache, HeuristicCache, NoGameCache, NoHeuristicCache};
+    use rand::prelude::SliceRandom;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    type Widening = UltTTTProgressiveWidening<UltTTTHeuristic, UltTTTMCTSConfig>;
+    type Reference =
+        HeuristicProgressiveWidening<UltTTTMCTSGame, UltTTTHeuristic, UltTTTMCTSConfig>;
+
+    #[test]
+    fn test_forced_move_is_expanded_without_heuristic() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        let mut heuristic_cache: NoHeuristicCache<UltTTT, UltTTTMove> = NoHeuristicCache::new();
+        let mcts_config = UltTTTMCTSConfig::default();
+        let heuristic_config = UltTTTHeuristicConfig::default();
+        let mut forced_moves = 0;
+        for _ in 0..20 {
+            let mut state = UltTTT::new();
+            while UltTTTMCTSGame::evaluate(&state, &mut game_cache).is_none() {
+                let mut widening = Widening::new(
+                    &state,
+                    &mut game_cache,
+                    &mut heuristic_cache,
+                    &heuristic_config,
+                );
+                let expanded =
+                    widening.expandable_moves(0, 0, &state, &mcts_config, &heuristic_config);
+                if state.legal_move_count() == 1 {
+                    forced_moves += 1;
+                    assert_eq!(expanded, state.legal_moves().as_slice());
+                    assert!(!widening.should_expand(1, 1, &mcts_config, &heuristic_config));
+                } else {
+                    // moves of equal heuristic value are shuffled, therefore compare only number
+                    let mut reference = Reference::new(
+                        &state,
+                        &mut game_cache,
+                        &mut heuristic_cache,
+                        &heuristic_config,
+                    );
+                    let reference =
+                        reference.expandable_moves(0, 0, &state, &mcts_config, &heuristic_config);
+                    assert_eq!(expanded.len(), reference.len());
+                }
+                let mv = *state.legal_moves().as_slice().choose(&mut rng).unwrap();
+                state = UltTTTMCTSGame::apply_move(&state, &mv, &mut game_cache);
+            }
+        }
+        assert!(forced_moves > 0);
+    }
+}
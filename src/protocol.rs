@@ -0,0 +1,288 @@
+// codingame protocol: parsing of turn input and formatting of actions
+// Each turn codingame sends "row col" of opponent move ("-1 -1" if there is none), the number of
+// valid actions and one line "row col" for each valid action.
+
+use super::{MoveError, NextActionConstraint, UltTTT, UltTTTMCTSGame, UltTTTMove};
+use my_lib::my_mcts::MCTSGame;
+
+use std::fmt::Display;
+use std::io::BufRead;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnInput {
+    // None, if own bot is start player and this is the first turn
+    pub opponent_move: Option<UltTTTMove>,
+    pub valid_actions: Vec<UltTTTMove>,
+}
+
+#[derive(Debug)]
+pub enum ProtocolError {
+    Io(std::io::Error),
+    UnexpectedEndOfInput,
+    InvalidLine(String),
+    InvalidNumber(String),
+    InvalidAction { row: i32, col: i32 },
+    MoveOutOfRange(MoveError),
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProtocolError::Io(err) => write!(f, "failed to read input: {}", err),
+            ProtocolError::UnexpectedEndOfInput => write!(f, "input ended in the middle of a turn"),
+            ProtocolError::InvalidLine(line) => {
+                write!(f, "expected two values \"row col\", got {:?}", line)
+            }
+            ProtocolError::InvalidNumber(value) => write!(f, "{:?} is not a number", value),
+            ProtocolError::InvalidAction { row, col } => {
+                write!(f, "row {} col {} is no valid action", row, col)
+            }
+            ProtocolError::MoveOutOfRange(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+impl From<std::io::Error> for ProtocolError {
+    fn from(err: std::io::Error) -> Self {
+        ProtocolError::Io(err)
+    }
+}
+
+// valid actions of tracked state do not match valid actions of codingame
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidActionsMismatch {
+    // valid for codingame, but not for tracked state
+    pub missing: Vec<UltTTTMove>,
+    // valid for tracked state, but not for codingame
+    pub unexpected: Vec<UltTTTMove>,
+}
+
+impl Display for ValidActionsMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let actions = |moves: &[UltTTTMove]| {
+            moves
+                .iter()
+                .map(|mv| format_action(*mv))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        write!(
+            f,
+            "missing valid actions [{}], unexpected valid actions [{}]",
+            actions(&self.missing),
+            actions(&self.unexpected)
+        )
+    }
+}
+
+// input of turn as sent by codingame, e.g. by local referee
+impl Display for TurnInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.opponent_move {
+            Some(mv) => writeln!(f, "{}", format_action(mv))?,
+            None => writeln!(f, "-1 -1")?,
+        }
+        writeln!(f, "{}", self.valid_actions.len())?;
+        for mv in self.valid_actions.iter() {
+            writeln!(f, "{}", format_action(*mv))?;
+        }
+        Ok(())
+    }
+}
+
+impl TurnInput {
+    // returns None at end of input before start of next turn
+    pub fn read<R: BufRead>(input: &mut R) -> Result<Option<TurnInput>, ProtocolError> {
+        let Some(line) = read_line(input)? else {
+            return Ok(None);
+        };
+        let opponent_move = parse_action(&line)?;
+        let line = read_line(input)?.ok_or(ProtocolError::UnexpectedEndOfInput)?;
+        let valid_action_count = parse_number::<usize>(line.trim())?;
+        let mut valid_actions = Vec::with_capacity(valid_action_count);
+        for _ in 0..valid_action_count {
+            let line = read_line(input)?.ok_or(ProtocolError::UnexpectedEndOfInput)?;
+            // "-1 -1" is no valid action
+            let valid_action =
+                parse_action(&line)?.ok_or(ProtocolError::InvalidAction { row: -1, col: -1 })?;
+            valid_actions.push(valid_action);
+        }
+        Ok(Some(TurnInput {
+            opponent_move,
+            valid_actions,
+        }))
+    }
+    // compares valid actions of codingame with valid actions of tracked state
+    pub fn check_valid_actions(&self, state: &UltTTT) -> Result<(), ValidActionsMismatch> {
+        let expected = expected_valid_actions(state);
+        let missing: Vec<UltTTTMove> = self
+            .valid_actions
+            .iter()
+            .filter(|mv| !expected.contains(mv))
+            .copied()
+            .collect();
+        let unexpected: Vec<UltTTTMove> = expected
+            .iter()
+            .filter(|mv| !self.valid_actions.contains(mv))
+            .copied()
+            .collect();
+        if missing.is_empty() && unexpected.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidActionsMismatch {
+                missing,
+                unexpected,
+            })
+        }
+    }
+}
+
+// valid actions, which codingame sends for state: available_moves() restricts first move of game
+// to mini board MM by choice, while codingame allows every cell
+pub fn expected_valid_actions(state: &UltTTT) -> Vec<UltTTTMove> {
+    if state.next_action_constraint == NextActionConstraint::Init {
+        return (0..9)
+            .flat_map(|y| (0..9).map(move |x| (x, y)))
+            .filter_map(|cell| UltTTTMove::try_from(cell).ok())
+            .collect();
+    }
+    UltTTTMCTSGame::available_moves(state).collect()
+}
+
+// parses "row col"; "-1 -1" is no action
+pub fn parse_action(line: &str) -> Result<Option<UltTTTMove>, ProtocolError> {
+    match parse_row_col(line)? {
+        (-1, -1) => Ok(None),
+        (row, col) => {
+            let (Ok(x), Ok(y)) = (u8::try_from(col), u8::try_from(row)) else {
+                return Err(ProtocolError::InvalidAction { row, col });
+            };
+            UltTTTMove::try_from((x, y))
+                .map(Some)
+                .map_err(ProtocolError::MoveOutOfRange)
+        }
+    }
+}
+
+// action in output format of codingame "row col"
+pub fn format_action(mv: UltTTTMove) -> String {
+    let (x, y) = <(u8, u8)>::from(mv);
+    format!("{} {}", y, x)
+}
+
+fn read_line<R: BufRead>(input: &mut R) -> Result<Option<String>, ProtocolError> {
+    let mut input_line = String::new();
+    if input.read_line(&mut input_line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(input_line))
+}
+
+fn parse_row_col(line: &str) -> Result<(i32, i32), ProtocolError> {
+    let values: Vec<&str> = line.split_whitespace().collect();
+    match values.as_slice() {
+        [row, col] => Ok((parse_number(row)?, parse_number(col)?)),
+        _ => Err(ProtocolError::InvalidLine(line.trim_end().to_string())),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, ProtocolError> {
+    value
+        .parse()
+        .map_err(|_| ProtocolError::InvalidNumber(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn mv(row: u8, col: u8) -> UltTTTMove {
+        UltTTTMove::try_from((col, row)).unwrap()
+    }
+
+    #[test]
+    fn test_read_turn_input() {
+        let mut input = Cursor::new("-1 -1\n2\n4 4\n0 8\n3 5\n1\n3 3\n");
+        let turn = TurnInput::read(&mut input).unwrap().unwrap();
+        assert_eq!(turn.opponent_move, None);
+        assert_eq!(turn.valid_actions, vec![mv(4, 4), mv(0, 8)]);
+        let turn = TurnInput::read(&mut input).unwrap().unwrap();
+        assert_eq!(turn.opponent_move, Some(mv(3, 5)));
+        assert_eq!(turn.valid_actions, vec![mv(3, 3)]);
+        assert!(TurnInput::read(&mut input).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_display_turn_input_round_trip() {
+        let turn = TurnInput {
+            opponent_move: Some(mv(3, 5)),
+            valid_actions: vec![mv(3, 3), mv(5, 4)],
+        };
+        assert_eq!(turn.to_string(), "3 5\n2\n3 3\n5 4\n");
+        let read = TurnInput::read(&mut Cursor::new(turn.to_string())).unwrap();
+        assert_eq!(read, Some(turn));
+        let first_turn = TurnInput {
+            opponent_move: None,
+            valid_actions: Vec::new(),
+        };
+        assert_eq!(first_turn.to_string(), "-1 -1\n0\n");
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        let read = |text: &str| TurnInput::read(&mut Cursor::new(text.to_string()));
+        assert!(matches!(
+            read("4 4\n"),
+            Err(ProtocolError::UnexpectedEndOfInput)
+        ));
+        assert!(matches!(read("4\n0\n"), Err(ProtocolError::InvalidLine(_))));
+        assert!(matches!(
+            read("4 a\n0\n"),
+            Err(ProtocolError::InvalidNumber(_))
+        ));
+        assert!(matches!(
+            read("-2 4\n0\n"),
+            Err(ProtocolError::InvalidAction { row: -2, col: 4 })
+        ));
+        assert!(matches!(
+            read("9 0\n0\n"),
+            Err(ProtocolError::MoveOutOfRange(MoveError::OutOfRange((0, 9))))
+        ));
+        assert!(matches!(
+            read("4 4\n1\n-1 -1\n"),
+            Err(ProtocolError::InvalidAction { row: -1, col: -1 })
+        ));
+    }
+
+    #[test]
+    fn test_check_valid_actions() {
+        let state = UltTTT::new();
+        // first move of game may be played in every cell
+        let turn = TurnInput {
+            opponent_move: None,
+            valid_actions: expected_valid_actions(&state),
+        };
+        assert_eq!(turn.valid_actions.len(), 81);
+        assert!(turn.check_valid_actions(&state).is_ok());
+
+        let state = state.try_apply(&mv(4, 4)).unwrap();
+        let mut valid_actions = expected_valid_actions(&state);
+        assert_eq!(valid_actions.len(), 8);
+        let removed = valid_actions.pop().unwrap();
+        valid_actions.push(mv(0, 0));
+        let turn = TurnInput {
+            opponent_move: Some(mv(4, 4)),
+            valid_actions,
+        };
+        assert_eq!(
+            turn.check_valid_actions(&state),
+            Err(ValidActionsMismatch {
+                missing: vec![mv(0, 0)],
+                unexpected: vec![removed],
+            })
+        );
+    }
+}
@@ -0,0 +1,187 @@
+// rule variants of UltTTT
+// Default rules are the rules of codingame.
+
+use super::{UltTTT, LINE_MASKS, MINI_BOARD_MASK, WIN_TABLE};
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum TieBreak {
+    // meta board without winner is a draw
+    Draw,
+    // meta board without winner is won by player with more won mini boards
+    #[default]
+    CountWonBoards,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum DecidedBoardPolicy {
+    // won and tied mini boards are closed; a player sent to a closed mini board has free choice
+    #[default]
+    FreeChoice,
+    // won mini boards stay playable until they are full; a player sent to a full mini board
+    // has free choice. The first player to win a mini board keeps it.
+    PlayUntilFull,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct RuleSet {
+    pub tie_break: TieBreak,
+    // tied mini boards count for both players on lines of meta board; a line needs at least
+    // one mini board won by player
+    pub tied_boards_count_for_both: bool,
+    pub decided_board_policy: DecidedBoardPolicy,
+}
+
+impl RuleSet {
+    pub const CODINGAME: RuleSet = RuleSet {
+        tie_break: TieBreak::CountWonBoards,
+        tied_boards_count_for_both: false,
+        decided_board_policy: DecidedBoardPolicy::FreeChoice,
+    };
+    pub const CLASSIC: RuleSet = RuleSet {
+        tie_break: TieBreak::Draw,
+        tied_boards_count_for_both: false,
+        decided_board_policy: DecidedBoardPolicy::FreeChoice,
+    };
+    pub const TIED_BOARDS_COUNT_FOR_BOTH: RuleSet = RuleSet {
+        tie_break: TieBreak::Draw,
+        tied_boards_count_for_both: true,
+        decided_board_policy: DecidedBoardPolicy::FreeChoice,
+    };
+}
+
+impl UltTTT {
+    pub fn with_rules(rules: RuleSet) -> Self {
+        let mut state = UltTTT::new();
+        state.rules = rules;
+        state
+    }
+    pub fn rules(&self) -> RuleSet {
+        self.rules
+    }
+    // mini boards, which are full without a winner
+    pub(crate) fn get_tied_boards(&self) -> u16 {
+        self.closed_boards & !(self.won_boards[0] | self.won_boards[1]) & MINI_BOARD_MASK
+    }
+    pub(crate) fn wins_meta_board(&self, player: usize) -> bool {
+        let won_boards = self.won_boards[player];
+        if !self.rules.tied_boards_count_for_both {
+            return WIN_TABLE[won_boards as usize];
+        }
+        let counting_boards = won_boards | self.get_tied_boards();
+        LINE_MASKS
+            .iter()
+            .any(|&line| counting_boards & line == line && won_boards & line != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{UltTTTMCTSGame, UltTTTMove};
+    use my_lib::my_mcts::{GameCache, MCTSGame, NoGameCache};
+    use my_lib::my_tic_tac_toe::TicTacToeStatus;
+    use rand::prelude::SliceRandom;
+
+    fn meta_board(rules: RuleSet, first: u16, second: u16, tied: u16) -> UltTTT {
+        let mut state = UltTTT::with_rules(rules);
+        state.won_boards = [first, second];
+        state.closed_boards = first | second | tied;
+        state
+    }
+
+    #[test]
+    fn test_tie_break_and_tied_boards() {
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        // First: TL, TM, MR, BL; Second: TR, ML, BR; tied: MM, BM
+        // only with tied boards First wins with column TM, MM, BM
+        let (first, second, tied) = (0b0_0110_0011, 0b1_0000_1100, 0b0_1001_0000);
+        let codingame = meta_board(RuleSet::CODINGAME, first, second, tied);
+        assert_eq!(codingame.get_status(), TicTacToeStatus::Tie);
+        assert_eq!(
+            UltTTTMCTSGame::evaluate(&codingame, &mut game_cache),
+            Some(1.0)
+        );
+        let classic = meta_board(RuleSet::CLASSIC, first, second, tied);
+        assert_eq!(classic.get_status(), TicTacToeStatus::Tie);
+        assert_eq!(
+            UltTTTMCTSGame::evaluate(&classic, &mut game_cache),
+            Some(0.5)
+        );
+        let tied_count = meta_board(RuleSet::TIED_BOARDS_COUNT_FOR_BOTH, first, second, tied);
+        assert_eq!(tied_count.get_status(), TicTacToeStatus::First);
+        assert_eq!(
+            UltTTTMCTSGame::evaluate(&tied_count, &mut game_cache),
+            Some(1.0)
+        );
+
+        // a line of tied boards counts for nobody
+        // First: TL, TR, BM; Second: TM, BL, BR; tied: ML, MM, MR
+        let (first, second, tied) = (0b0_1000_0101, 0b1_0100_0010, 0b0_0011_1000);
+        let tied_count = meta_board(RuleSet::TIED_BOARDS_COUNT_FOR_BOTH, first, second, tied);
+        assert_eq!(tied_count.get_status(), TicTacToeStatus::Tie);
+        assert_eq!(
+            UltTTTMCTSGame::evaluate(&tied_count, &mut game_cache),
+            Some(0.5)
+        );
+    }
+
+    #[test]
+    fn test_rule_sets_with_free_choice_share_move_generation() {
+        let mut rng = rand::thread_rng();
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        for _ in 0..200 {
+            let mut codingame = UltTTT::with_rules(RuleSet::CODINGAME);
+            let mut classic = UltTTT::with_rules(RuleSet::CLASSIC);
+            while let (None, None) = (
+                UltTTTMCTSGame::evaluate(&codingame, &mut game_cache),
+                UltTTTMCTSGame::evaluate(&classic, &mut game_cache),
+            ) {
+                let moves: Vec<UltTTTMove> = codingame.legal_moves().into_iter().collect();
+                assert_eq!(moves.as_slice(), classic.legal_moves().as_slice());
+                let mv = moves.choose(&mut rng).unwrap();
+                codingame = UltTTTMCTSGame::apply_move(&codingame, mv, &mut game_cache);
+                classic = UltTTTMCTSGame::apply_move(&classic, mv, &mut game_cache);
+            }
+            let codingame_score = UltTTTMCTSGame::evaluate(&codingame, &mut game_cache).unwrap();
+            let classic_score = UltTTTMCTSGame::evaluate(&classic, &mut game_cache).unwrap();
+            if codingame.get_status() == TicTacToeStatus::Tie {
+                assert_eq!(classic_score, 0.5);
+            } else {
+                assert_eq!(codingame_score, classic_score);
+            }
+        }
+    }
+
+    #[test]
+    fn test_play_until_full() {
+        let rules = RuleSet {
+            decided_board_policy: DecidedBoardPolicy::PlayUntilFull,
+            ..RuleSet::CLASSIC
+        };
+        let mut rng = rand::thread_rng();
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        for _ in 0..200 {
+            let mut state = UltTTT::with_rules(rules);
+            while UltTTTMCTSGame::evaluate(&state, &mut game_cache).is_none() {
+                let moves: Vec<UltTTTMove> = state.legal_moves().into_iter().collect();
+                let mv = moves.choose(&mut rng).unwrap();
+                let next_state = UltTTTMCTSGame::apply_move(&state, mv, &mut game_cache);
+                // only full mini boards are closed
+                for status_index in 0..9 {
+                    let is_full = next_state.get_vacant_cells(status_index) == 0;
+                    let is_closed = next_state.closed_boards & (1 << status_index) != 0;
+                    assert_eq!(is_full, is_closed);
+                }
+                // won mini boards keep their owner
+                assert_eq!(next_state.won_boards[0] & next_state.won_boards[1], 0);
+                for player in 0..2 {
+                    assert_eq!(
+                        state.won_boards[player] & next_state.won_boards[player],
+                        state.won_boards[player]
+                    );
+                }
+                state = next_state;
+            }
+        }
+    }
+}
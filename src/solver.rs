@@ -0,0 +1,303 @@
+// exact endgame solver of UltTTT
+// Negamax with alpha-beta pruning and a transposition table. Game values only depend on state
+// and rules, therefore entries of transposition table stay valid across searches with the same
+// rules. Since rules are no part of zobrist key, table is cleared, if rules of searched state change.
+
+use super::{
+    BitIter, RuleSet, UltTTT, UltTTTMCTSGame, UltTTTMove, ZobristHashMap, MINI_BOARD_MASK,
+};
+use my_lib::my_mcts::{GameCache, MCTSGame, NoGameCache};
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+
+use std::time::{Duration, Instant};
+
+// table is cleared, if it grows beyond this size
+const MAX_TABLE_SIZE: usize = 2_000_000;
+// check time out after this number of nodes
+const TIME_CHECK_INTERVAL: usize = 1_024;
+
+// game theoretic value from perspective of player to move
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum GameValue {
+    Loss = -1,
+    Draw = 0,
+    Win = 1,
+}
+
+impl GameValue {
+    fn from_score(score: i8) -> Self {
+        match score {
+            1 => GameValue::Win,
+            0 => GameValue::Draw,
+            _ => GameValue::Loss,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SolverResult {
+    pub value: GameValue,
+    pub best_move: UltTTTMove,
+    // number of searched nodes
+    pub nodes: usize,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Copy, Clone)]
+struct TableEntry {
+    score: i8,
+    bound: Bound,
+    best_move: Option<UltTTTMove>,
+}
+
+pub struct EndgameSolver {
+    // solver only starts search, if number of playable cells is below or equal to this limit
+    pub max_empty_cells: u32,
+    table: ZobristHashMap<TableEntry>,
+    // rules of all entries of table
+    rules: RuleSet,
+    game_cache: NoGameCache<UltTTT, UltTTTMove>,
+    nodes: usize,
+    deadline: Option<Instant>,
+    aborted: bool,
+}
+
+impl UltTTT {
+    // vacant cells of all mini boards, which are not closed
+    pub fn count_playable_cells(&self) -> u32 {
+        BitIter(!self.closed_boards & MINI_BOARD_MASK)
+            .map(|status_index| self.get_vacant_cells(status_index).count_ones())
+            .sum()
+    }
+}
+
+impl EndgameSolver {
+    pub fn new(max_empty_cells: u32) -> Self {
+        EndgameSolver {
+            max_empty_cells,
+            table: ZobristHashMap::default(),
+            rules: RuleSet::default(),
+            game_cache: NoGameCache::new(),
+            nodes: 0,
+            deadline: None,
+            aborted: false,
+        }
+    }
+    pub fn is_applicable(&self, state: &UltTTT) -> bool {
+        state.get_status() == TicTacToeStatus::Vacant
+            && state.count_playable_cells() <= self.max_empty_cells
+    }
+    // returns exact value and best move, if search finishes before time out
+    pub fn solve(&mut self, state: &UltTTT, time_out: Duration) -> Option<SolverResult> {
+        self.solve_until(state, Some(Instant::now() + time_out))
+    }
+    // search without time out
+    pub fn solve_exact(&mut self, state: &UltTTT) -> Option<SolverResult> {
+        self.solve_until(state, None)
+    }
+    fn solve_until(&mut self, state: &UltTTT, deadline: Option<Instant>) -> Option<SolverResult> {
+        if !self.is_applicable(state) {
+            return None;
+        }
+        if self.table.len() > MAX_TABLE_SIZE || state.rules() != self.rules {
+            self.table.clear();
+            self.rules = state.rules();
+        }
+        self.nodes = 0;
+        self.deadline = deadline;
+        self.aborted = false;
+        // with full window score of root is exact, even if it is stored as bound
+        let score = self.negamax(state, -1, 1)?;
+        // root is never terminal, therefore its entry always contains a best move
+        let best_move = self.table.get(&state.zobrist())?.best_move?;
+        Some(SolverResult {
+            value: GameValue::from_score(score),
+            best_move,
+            nodes: self.nodes,
+        })
+    }
+    fn is_time_out(&mut self) -> bool {
+        if !self.aborted && self.nodes % TIME_CHECK_INTERVAL == 0 {
+            if let Some(deadline) = self.deadline {
+                self.aborted = Instant::now() >= deadline;
+            }
+        }
+        self.aborted
+    }
+    fn terminal_score(&mut self, state: &UltTTT) -> Option<i8> {
+        let score = UltTTTMCTSGame::evaluate(state, &mut self.game_cache)?;
+        // evaluate() returns score from perspective of First
+        let score = if score > 0.5 {
+            1
+        } else if score < 0.5 {
+            -1
+        } else {
+            0
+        };
+        Some(match state.current_player {
+            TicTacToeStatus::First => score,
+            _ => -score,
+        })
+    }
+    // returns None, if search was aborted
+    fn negamax(&mut self, state: &UltTTT, mut alpha: i8, mut beta: i8) -> Option<i8> {
+        self.nodes += 1;
+        if self.is_time_out() {
+            return None;
+        }
+        if let Some(score) = self.terminal_score(state) {
+            return Some(score);
+        }
+        let original_alpha = alpha;
+        let mut table_move = None;
+        if let Some(entry) = self.table.get(&state.zobrist()) {
+            match entry.bound {
+                Bound::Exact => return Some(entry.score),
+                Bound::Lower => alpha = alpha.max(entry.score),
+                Bound::Upper => beta = beta.min(entry.score),
+            }
+            if alpha >= beta {
+                return Some(entry.score);
+            }
+            table_move = entry.best_move;
+        }
+
+        let mut moves = state.legal_moves();
+        // search best move of previous search first
+        let moves = moves.as_mut_slice();
+        if let Some(table_move) = table_move {
+            if let Some(index) = moves.iter().position(|mv| *mv == table_move) {
+                moves.swap(0, index);
+            }
+        }
+        let mut best_score = -2;
+        let mut best_move = None;
+        for mv in moves.iter() {
+            let child = UltTTTMCTSGame::apply_move(state, mv, &mut self.game_cache);
+            let score = -self.negamax(&child, -beta, -alpha)?;
+            if score > best_score {
+                best_score = score;
+                best_move = Some(*mv);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.insert(
+            state.zobrist(),
+            TableEntry {
+                score: best_score,
+                bound,
+                best_move,
+            },
+        );
+        Some(best_score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::SliceRandom;
+
+    // plain minimax without pruning and table
+    fn minimax(state: &UltTTT, game_cache: &mut NoGameCache<UltTTT, UltTTTMove>) -> i8 {
+        if let Some(score) = UltTTTMCTSGame::evaluate(state, game_cache) {
+            let score = (2.0 * score - 1.0) as i8;
+            return match state.current_player {
+                TicTacToeStatus::First => score,
+                _ => -score,
+            };
+        }
+        state
+            .legal_moves()
+            .iter()
+            .map(|mv| {
+                -minimax(
+                    &UltTTTMCTSGame::apply_move(state, mv, game_cache),
+                    game_cache,
+                )
+            })
+            .max()
+            .unwrap()
+    }
+
+    fn random_endgame_position(max_empty_cells: u32) -> UltTTT {
+        let mut rng = rand::thread_rng();
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        loop {
+            let mut state = UltTTT::new();
+            while UltTTTMCTSGame::evaluate(&state, &mut game_cache).is_none() {
+                if state.count_playable_cells() <= max_empty_cells {
+                    return state;
+                }
+                let moves: Vec<UltTTTMove> = state.legal_moves().into_iter().collect();
+                let mv = moves.choose(&mut rng).unwrap();
+                state = UltTTTMCTSGame::apply_move(&state, mv, &mut game_cache);
+            }
+        }
+    }
+
+    #[test]
+    fn test_solver_matches_minimax() {
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        let mut solver = EndgameSolver::new(8);
+        for _ in 0..200 {
+            let state = random_endgame_position(8);
+            let expected = minimax(&state, &mut game_cache);
+            let result = solver.solve_exact(&state).unwrap();
+            assert_eq!(result.value as i8, expected);
+            // best move keeps value
+            let child = UltTTTMCTSGame::apply_move(&state, &result.best_move, &mut game_cache);
+            assert_eq!(-minimax(&child, &mut game_cache), expected);
+        }
+    }
+
+    #[test]
+    fn test_table_is_cleared_if_rules_change() {
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        // position, whose value depends on tie break
+        let (codingame, classic) = loop {
+            let codingame = random_endgame_position(6);
+            let mut classic = codingame;
+            classic.rules = RuleSet::CLASSIC;
+            if minimax(&codingame, &mut game_cache) != minimax(&classic, &mut game_cache) {
+                break (codingame, classic);
+            }
+        };
+        assert_eq!(codingame.zobrist(), classic.zobrist());
+        let mut solver = EndgameSolver::new(6);
+        for state in [codingame, classic, codingame] {
+            let result = solver.solve_exact(&state).unwrap();
+            assert_eq!(result.value as i8, minimax(&state, &mut game_cache));
+        }
+    }
+
+    #[test]
+    fn test_solver_respects_limit_and_time_out() {
+        let mut solver = EndgameSolver::new(10);
+        assert!(solver.solve_exact(&UltTTT::new()).is_none());
+        solver.max_empty_cells = 81;
+        let mut state = UltTTT::new();
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        let mv = state.legal_moves().iter().next().copied().unwrap();
+        state = UltTTTMCTSGame::apply_move(&state, &mv, &mut game_cache);
+        // full game tree cannot be searched in 10 ms
+        assert!(solver.solve(&state, Duration::from_millis(10)).is_none());
+    }
+}
@@ -0,0 +1,192 @@
+// sequential probability ratio test (SPRT) of match scores
+// H0: expected score of candidate is score0, H1: expected score is score1. Log-likelihood ratio
+// (LLR) is approximated with generalized SPRT by mean and variance of samples:
+// LLR = n * (score1 - score0) * (2 * mean - score0 - score1) / (2 * variance)
+// Samples are scores of single games (trinomial: loss, draw, win) or mean scores of pairs of games
+// with swapped start player (pentanomial), which removes variance of start player advantage.
+// Test stops, if LLR leaves bounds given by error rates alpha (accept H1, although H0 is true)
+// and beta (accept H0, although H1 is true).
+
+// one virtual loss and one virtual win keep variance positive, if all samples are equal
+const PRIOR_SAMPLES: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SprtModel {
+    // samples are single games
+    Trinomial,
+    // samples are pairs of games; matches 2 * k and 2 * k + 1 form a pair
+    #[default]
+    Pentanomial,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtDecision {
+    Continue,
+    // candidate is not better than score0
+    AcceptH0,
+    // candidate is at least as good as score1
+    AcceptH1,
+}
+
+// counts of game scores and pair scores in order of games
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SprtCounts {
+    // losses, draws, wins
+    pub games: [usize; 3],
+    // pairs with sum of scores 0.0, 0.5, 1.0, 1.5 and 2.0
+    pub pairs: [usize; 5],
+    // first game of an incomplete pair
+    pending: Option<usize>,
+}
+
+impl SprtCounts {
+    pub fn push(&mut self, score: f64) {
+        let index = match score {
+            s if s > 0.5 => 2,
+            s if s < 0.5 => 0,
+            _ => 1,
+        };
+        self.games[index] += 1;
+        match self.pending.take() {
+            Some(first) => self.pairs[first + index] += 1,
+            None => self.pending = Some(index),
+        }
+    }
+    pub fn num_games(&self) -> usize {
+        self.games.iter().sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub score0: f64,
+    pub score1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    pub model: SprtModel,
+}
+
+impl Sprt {
+    // error rates of 5% and pentanomial model
+    pub fn new(score0: f64, score1: f64) -> Self {
+        Sprt {
+            score0,
+            score1,
+            alpha: 0.05,
+            beta: 0.05,
+            model: SprtModel::default(),
+        }
+    }
+    pub fn with_error_rates(mut self, alpha: f64, beta: f64) -> Self {
+        self.alpha = alpha;
+        self.beta = beta;
+        self
+    }
+    pub fn with_model(mut self, model: SprtModel) -> Self {
+        self.model = model;
+        self
+    }
+    // lower bound accepts H0, upper bound accepts H1
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+    pub fn llr(&self, counts: &SprtCounts) -> f64 {
+        let samples: Vec<(f64, f64)> = match self.model {
+            SprtModel::Trinomial => counts
+                .games
+                .iter()
+                .enumerate()
+                .map(|(index, count)| (index as f64 / 2.0, *count as f64))
+                .collect(),
+            SprtModel::Pentanomial => counts
+                .pairs
+                .iter()
+                .enumerate()
+                .map(|(index, count)| (index as f64 / 4.0, *count as f64))
+                .collect(),
+        };
+        let num_samples: f64 = samples.iter().map(|(_, count)| count).sum();
+        if num_samples == 0.0 {
+            return 0.0;
+        }
+        let samples = [(0.0, PRIOR_SAMPLES), (1.0, PRIOR_SAMPLES)]
+            .into_iter()
+            .chain(samples);
+        let n = num_samples + 2.0 * PRIOR_SAMPLES;
+        let mean = samples
+            .clone()
+            .map(|(score, count)| score * count)
+            .sum::<f64>()
+            / n;
+        let variance = samples
+            .map(|(score, count)| (score - mean).powi(2) * count)
+            .sum::<f64>()
+            / n;
+        n * (self.score1 - self.score0) * (2.0 * mean - self.score0 - self.score1)
+            / (2.0 * variance)
+    }
+    pub fn decision(&self, llr: f64) -> SprtDecision {
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtDecision::AcceptH1
+        } else if llr <= lower {
+            SprtDecision::AcceptH0
+        } else {
+            SprtDecision::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_of_pairs() {
+        let mut counts = SprtCounts::default();
+        for score in [1.0, 0.0, 1.0, 0.5, 0.5] {
+            counts.push(score);
+        }
+        assert_eq!(counts.games, [1, 2, 2]);
+        // win + loss and win + draw; last game is pending
+        assert_eq!(counts.pairs, [0, 0, 1, 1, 0]);
+        assert_eq!(counts.num_games(), 5);
+    }
+
+    #[test]
+    fn test_sprt_decisions() {
+        let sprt = Sprt::new(0.5, 0.6);
+        let (lower, upper) = sprt.bounds();
+        assert!((upper - (0.95f64 / 0.05).ln()).abs() < 1e-9);
+        assert!((lower + upper).abs() < 1e-9);
+        assert_eq!(sprt.llr(&SprtCounts::default()), 0.0);
+
+        let llr_of = |sprt: &Sprt, scores: &[f64]| {
+            let mut counts = SprtCounts::default();
+            for score in scores {
+                counts.push(*score);
+            }
+            sprt.llr(&counts)
+        };
+        // candidate wins every pair
+        let wins = [1.0, 1.0, 1.0, 0.5].repeat(8);
+        let llr = llr_of(&sprt, &wins);
+        assert_eq!(sprt.decision(llr), SprtDecision::AcceptH1);
+        // candidate loses every pair
+        let losses = [0.0, 0.0, 0.0, 0.5].repeat(8);
+        let llr = llr_of(&sprt, &losses);
+        assert_eq!(sprt.decision(llr), SprtDecision::AcceptH0);
+        // few games of even score do not decide
+        let even = [1.0, 0.0, 0.5, 1.0, 0.0, 0.5];
+        let llr = llr_of(&sprt, &even);
+        assert!(llr < 0.0);
+        assert_eq!(sprt.decision(llr), SprtDecision::Continue);
+        // pentanomial model removes variance of start player advantage and decides faster
+        let trinomial = sprt.with_model(SprtModel::Trinomial);
+        let alternating = [1.0, 0.0].repeat(4);
+        assert!(llr_of(&sprt, &alternating).abs() > llr_of(&trinomial, &alternating).abs());
+    }
+}
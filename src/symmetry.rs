@@ -0,0 +1,231 @@
+// dihedral symmetries of UltTTT
+// Each symmetry acts on meta board and on every mini board at the same time.
+
+use super::{NextActionConstraint, UltTTT, UltTTTMove, CELL_INDICES, MINI_BOARD_MASK};
+use my_lib::my_map_3x3::CellIndex3x3;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Symmetry {
+    #[default]
+    Identity,
+    // clockwise rotations
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    // mirror left <-> right
+    FlipHorizontal,
+    // mirror top <-> bottom
+    FlipVertical,
+    // mirror at diagonal TL - BR
+    FlipDiagonal,
+    // mirror at anti diagonal TR - BL
+    FlipAntiDiagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::FlipDiagonal,
+        Symmetry::FlipAntiDiagonal,
+    ];
+    // PERMUTATIONS[symmetry][index] is transformed index of index of a 3x3 board
+    const PERMUTATIONS: [[usize; 9]; 8] = [
+        [0, 1, 2, 3, 4, 5, 6, 7, 8],
+        [2, 5, 8, 1, 4, 7, 0, 3, 6],
+        [8, 7, 6, 5, 4, 3, 2, 1, 0],
+        [6, 3, 0, 7, 4, 1, 8, 5, 2],
+        [2, 1, 0, 5, 4, 3, 8, 7, 6],
+        [6, 7, 8, 3, 4, 5, 0, 1, 2],
+        [0, 3, 6, 1, 4, 7, 2, 5, 8],
+        [8, 5, 2, 7, 4, 1, 6, 3, 0],
+    ];
+    pub fn inverse(&self) -> Symmetry {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            _ => *self,
+        }
+    }
+    pub fn transform_index(&self, index: usize) -> usize {
+        Self::PERMUTATIONS[*self as usize][index]
+    }
+    pub fn transform_cell(&self, cell: CellIndex3x3) -> CellIndex3x3 {
+        CELL_INDICES[self.transform_index(usize::from(cell))]
+    }
+    fn transform_board_bits(&self, bits: u16) -> u16 {
+        (0..9)
+            .filter(|index| bits & (1 << index) != 0)
+            .fold(0, |transformed, index| {
+                transformed | 1 << self.transform_index(index)
+            })
+            & MINI_BOARD_MASK
+    }
+    fn transform_cell_bits(&self, bits: u128) -> u128 {
+        (0..81)
+            .filter(|cell| bits & (1 << cell) != 0)
+            .fold(0, |transformed, cell| {
+                transformed
+                    | 1 << (9 * self.transform_index(cell / 9) + self.transform_index(cell % 9))
+            })
+    }
+}
+
+impl NextActionConstraint {
+    pub fn transform(&self, symmetry: Symmetry) -> NextActionConstraint {
+        match self {
+            NextActionConstraint::MiniBoard(status_index) => {
+                NextActionConstraint::MiniBoard(symmetry.transform_cell(*status_index))
+            }
+            _ => *self,
+        }
+    }
+    fn order_key(&self) -> usize {
+        match self {
+            NextActionConstraint::Init => 0,
+            NextActionConstraint::None => 1,
+            NextActionConstraint::MiniBoard(status_index) => 2 + usize::from(*status_index),
+        }
+    }
+}
+
+impl UltTTTMove {
+    pub fn transform(&self, symmetry: Symmetry) -> UltTTTMove {
+        UltTTTMove {
+            status_index: symmetry.transform_cell(self.status_index),
+            mini_board_index: symmetry.transform_cell(self.mini_board_index),
+        }
+    }
+    pub fn inverse_transform(&self, symmetry: Symmetry) -> UltTTTMove {
+        self.transform(symmetry.inverse())
+    }
+}
+
+impl UltTTT {
+    pub fn transform(&self, symmetry: Symmetry) -> UltTTT {
+        if symmetry == Symmetry::Identity {
+            return *self;
+        }
+        let mut transformed = *self;
+        for player in 0..2 {
+            transformed.cells[player] = symmetry.transform_cell_bits(self.cells[player]);
+            transformed.won_boards[player] = symmetry.transform_board_bits(self.won_boards[player]);
+        }
+        transformed.closed_boards = symmetry.transform_board_bits(self.closed_boards);
+        transformed.next_action_constraint = self.next_action_constraint.transform(symmetry);
+        transformed.zobrist = transformed.calc_zobrist();
+        transformed
+    }
+    // returns canonical representative of all symmetric states and the symmetry, which
+    // transforms self into canonical representative
+    pub fn canonical(&self) -> (UltTTT, Symmetry) {
+        Symmetry::ALL
+            .iter()
+            .map(|symmetry| (self.transform(*symmetry), *symmetry))
+            .min_by_key(|(state, _)| {
+                (
+                    state.cells[0],
+                    state.cells[1],
+                    state.next_action_constraint.order_key(),
+                )
+            })
+            .expect("Symmetry::ALL is not empty")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml_linfa::{augment_features, FeatureExtraction};
+    use crate::{UltTTTMCTSGame, LINE_MASKS};
+    use my_lib::my_mcts::{GameCache, MCTSGame, NoGameCache};
+    use rand::prelude::SliceRandom;
+
+    #[test]
+    fn test_inverse_symmetry() {
+        for symmetry in Symmetry::ALL {
+            for index in 0..9 {
+                assert_eq!(
+                    symmetry
+                        .inverse()
+                        .transform_index(symmetry.transform_index(index)),
+                    index
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_symmetries_keep_lines() {
+        // a symmetry maps every line of a 3x3 board onto a line
+        for symmetry in Symmetry::ALL {
+            for line in LINE_MASKS {
+                assert!(LINE_MASKS.contains(&symmetry.transform_board_bits(line)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_transform_commutes_with_apply_move() {
+        let mut rng = rand::thread_rng();
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        for _ in 0..200 {
+            let mut state = UltTTT::new();
+            while UltTTTMCTSGame::evaluate(&state, &mut game_cache).is_none() {
+                let moves: Vec<UltTTTMove> = UltTTTMCTSGame::available_moves(&state).collect();
+                let mv = *moves.choose(&mut rng).unwrap();
+                let next_state = UltTTTMCTSGame::apply_move(&state, &mv, &mut game_cache);
+                for symmetry in Symmetry::ALL {
+                    let transformed = state.transform(symmetry);
+                    let transformed_move = mv.transform(symmetry);
+                    assert_eq!(transformed_move.inverse_transform(symmetry), mv);
+                    assert!(UltTTTMCTSGame::available_moves(&transformed)
+                        .any(|m| m == transformed_move));
+                    let transformed_next_state = UltTTTMCTSGame::apply_move(
+                        &transformed,
+                        &transformed_move,
+                        &mut game_cache,
+                    );
+                    assert!(transformed_next_state == next_state.transform(symmetry));
+                    assert!(transformed.transform(symmetry.inverse()) == state);
+                }
+                let (canonical, symmetry) = state.canonical();
+                assert!(canonical == state.transform(symmetry));
+                for other in Symmetry::ALL {
+                    let (other_canonical, _) = state.transform(other).canonical();
+                    assert!(other_canonical == canonical);
+                    assert_eq!(other_canonical.zobrist(), canonical.zobrist());
+                }
+                state = next_state;
+            }
+        }
+    }
+
+    #[test]
+    fn test_augmented_features_match_symmetric_states() {
+        let mut rng = rand::thread_rng();
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        for _ in 0..50 {
+            let mut state = UltTTT::new();
+            while UltTTTMCTSGame::evaluate(&state, &mut game_cache).is_none() {
+                let augmented = augment_features(&state.extract_canonical_features());
+                let mut expected: Vec<Vec<f64>> = Vec::new();
+                for symmetry in Symmetry::ALL {
+                    let features = state.transform(symmetry).extract_features();
+                    if !expected.contains(&features) {
+                        expected.push(features);
+                    }
+                }
+                assert_eq!(augmented.len(), expected.len());
+                assert!(expected.iter().all(|features| augmented.contains(features)));
+                let moves: Vec<UltTTTMove> = UltTTTMCTSGame::available_moves(&state).collect();
+                let mv = *moves.choose(&mut rng).unwrap();
+                state = UltTTTMCTSGame::apply_move(&state, &mv, &mut game_cache);
+            }
+        }
+    }
+}
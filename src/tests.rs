@@ -8,9 +8,11 @@ use std::{
 use super::old_heuristic::OldUltTTTHeuristic;
 use super::*;
 use my_lib::my_mcts::{
-    CachedUTC, DefaultSimulationPolicy, DynamicC, ExpandAll, HeuristicCutoff, MCTSAlgo,
-    NoHeuristic, NoTranspositionTable, PlainMCTS, PlainTTHashMap,
+    CachedUTC, DefaultSimulationPolicy, DynamicC, ExpandAll, GameCache, HeuristicCutoff,
+    HeuristicProgressiveWidening, MCTSAlgo, NoHeuristic, NoTranspositionTable, PlainMCTS,
+    PlainTTHashMap,
 };
+use rand::prelude::SliceRandom;
 
 pub type HPWDefaultTTTOldHeuristic =
     HeuristicProgressiveWidening<UltTTTMCTSGame, OldUltTTTHeuristic, UltTTTMCTSConfig>;
@@ -20,6 +22,49 @@ const TIME_OUT_SUCCESSIVE_TURNS: Duration = Duration::from_millis(95);
 const EXPECTED_NUM_NODES: usize = 220_000;
 const EXPECTED_NUM_NODES_PLAIN: usize = 400_000;
 
+#[test]
+fn test_bitboard_matches_tic_tac_toe_game_data() {
+    let mut rng = rand::thread_rng();
+    let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+    for _ in 0..1_000 {
+        let mut state = UltTTT::new();
+        while UltTTTMCTSGame::evaluate(&state, &mut game_cache).is_none() {
+            let status_map = state.get_status_map();
+            assert_eq!(state.get_status(), status_map.get_status());
+            for (status_index, status) in status_map.iter_map() {
+                assert_eq!(*status, state.get_mini_board(status_index).get_status());
+            }
+            // reference move generation with TicTacToeGameData
+            let open_boards: Vec<CellIndex3x3> = match state.next_action_constraint {
+                NextActionConstraint::Init => vec![CellIndex3x3::MM],
+                NextActionConstraint::MiniBoard(constraint) => vec![constraint],
+                NextActionConstraint::None => status_map
+                    .iter_map()
+                    .filter(|(_, c)| c.is_vacant())
+                    .map(|(i, _)| i)
+                    .collect(),
+            };
+            let expected_moves: Vec<UltTTTMove> = open_boards
+                .into_iter()
+                .flat_map(|status_index| {
+                    state
+                        .get_mini_board(status_index)
+                        .iter_map()
+                        .filter_map(|(i, c)| UltTTTMove::valid_move(status_index, i, c))
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            let moves: Vec<UltTTTMove> = UltTTTMCTSGame::available_moves(&state).collect();
+            assert_eq!(moves, expected_moves);
+            assert_eq!(state.legal_moves().as_slice(), &expected_moves[..]);
+            assert_eq!(state.legal_move_count(), expected_moves.len());
+            let mv = moves.choose(&mut rng).unwrap();
+            state = UltTTTMCTSGame::apply_move(&state, mv, &mut game_cache);
+        }
+        assert_eq!(state.get_status(), state.get_status_map().get_status());
+    }
+}
+
 #[test]
 fn test_mcts_ult_ttt_no_game_cache() {
     let mut wins = 0.0;
@@ -120,7 +165,7 @@ fn test_mcts_ult_ttt_no_game_cache() {
         }
         eprintln!("Game ended");
         eprintln!("{}", first_ult_ttt_game_data);
-        match first_ult_ttt_game_data.status_map.get_status() {
+        match first_ult_ttt_game_data.get_status() {
             TicTacToeStatus::First => {
                 eprintln!("first winner");
             }
@@ -245,7 +290,7 @@ fn test_mcts_ult_ttt_new_vs_old_heuristic() {
             }
         }
         eprint!("Game ended: ");
-        match first_ult_ttt_game_data.status_map.get_status() {
+        match first_ult_ttt_game_data.get_status() {
             TicTacToeStatus::First => {
                 eprintln!("first winner");
             }
@@ -387,7 +432,7 @@ fn test_mcts_ult_ttt_set_root_vs_reset_root_no_tt() {
             }
         }
         eprint!("Game ended: ");
-        match first_ult_ttt_game_data.status_map.get_status() {
+        match first_ult_ttt_game_data.get_status() {
             TicTacToeStatus::First => {
                 eprintln!("first winner");
             }
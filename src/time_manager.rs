@@ -0,0 +1,294 @@
+// adaptive time management of a turn
+// Time of turn is spent by game phase and number of legal moves. Search stops early, if lead in
+// visits of best move cannot be overtaken before time out, and is extended up to hard limit in
+// critical positions (unstable or close best move). Same policy is used by bot and run_match().
+
+use super::UltTTT;
+use my_lib::my_mcts::{
+    ExpansionPolicy, Heuristic, MCTSConfig, MCTSGame, MCTSNode, MCTSTree, PlainMCTS,
+    SimulationPolicy, TranspositionTable, UCTPolicy, UTCCache,
+};
+use std::time::{Duration, Instant};
+
+// visits of two most visited children of root
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RootLeaders {
+    // index of most visited child of root
+    pub best_index: usize,
+    pub best_visits: usize,
+    pub second_visits: usize,
+}
+
+pub trait RootStatistics {
+    fn root_leaders(&self) -> Option<RootLeaders>;
+}
+
+impl<G, H, MC, UC, TT, UP, EP, SP> RootStatistics for PlainMCTS<G, H, MC, UC, TT, UP, EP, SP>
+where
+    G: MCTSGame,
+    H: Heuristic<G>,
+    MC: MCTSConfig<G::Player>,
+    UC: UTCCache<G, UP, MC>,
+    TT: TranspositionTable<G::State, usize>,
+    UP: UCTPolicy<G, MC>,
+    EP: ExpansionPolicy<G, H, MC>,
+    SP: SimulationPolicy<G, H, MC>,
+{
+    fn root_leaders(&self) -> Option<RootLeaders> {
+        let root_id = self.tree.root_id()?;
+        let mut leaders: Option<RootLeaders> = None;
+        for (index, (child_id, _)) in self.tree.get_children(root_id).iter().enumerate() {
+            let visits = self.tree.get_node(*child_id).get_visits();
+            leaders = Some(match leaders {
+                None => RootLeaders {
+                    best_index: index,
+                    best_visits: visits,
+                    second_visits: 0,
+                },
+                // first child with max visits is best child, same as select_move()
+                Some(leaders) if visits > leaders.best_visits => RootLeaders {
+                    best_index: index,
+                    best_visits: visits,
+                    second_visits: leaders.best_visits,
+                },
+                Some(leaders) => RootLeaders {
+                    second_visits: leaders.second_visits.max(visits),
+                    ..leaders
+                },
+            });
+        }
+        leaders
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeManager {
+    // hard limits of codingame
+    pub first_turn_limit: Duration,
+    pub turn_limit: Duration,
+    // time of turn never exceeds limit minus safety margin (latency of input and output)
+    pub safety_margin: Duration,
+    // share of available time, which is spent in normal positions
+    pub base_share: f32,
+    // additional share at middle of game, where most decisions are made
+    pub mid_game_share: f32,
+    // factor of target time, if player has free choice of mini board
+    pub free_choice_factor: f32,
+    // best move is unstable, if it changed after this share of target time
+    pub stability_share: f32,
+    // best move is close, if second best move has at least this ratio of visits of best move
+    pub close_visits_ratio: f32,
+    // without adaptive time management each turn uses full available time
+    pub adaptive: bool,
+}
+
+impl Default for TimeManager {
+    fn default() -> Self {
+        TimeManager {
+            first_turn_limit: Duration::from_millis(1_000),
+            turn_limit: Duration::from_millis(100),
+            // same 85 ms of successive turns as fixed time out before time management
+            safety_margin: Duration::from_millis(15),
+            base_share: 0.8,
+            mid_game_share: 0.15,
+            free_choice_factor: 1.15,
+            stability_share: 0.5,
+            close_visits_ratio: 0.8,
+            adaptive: true,
+        }
+    }
+}
+
+impl TimeManager {
+    // fixed time outs of first and successive turns
+    pub fn fixed(first_turn: Duration, successive_turns: Duration) -> Self {
+        TimeManager {
+            first_turn_limit: first_turn,
+            turn_limit: successive_turns,
+            safety_margin: Duration::ZERO,
+            adaptive: false,
+            ..Default::default()
+        }
+    }
+    pub fn start_turn(&self, state: &UltTTT, first_turn: bool) -> TurnTimer {
+        let limit = if first_turn {
+            self.first_turn_limit
+        } else {
+            self.turn_limit
+        };
+        let max_time = limit.saturating_sub(self.safety_margin);
+        let target_time = if !self.adaptive || first_turn {
+            max_time
+        } else {
+            self.target_time(state, max_time)
+        };
+        TurnTimer {
+            start: Instant::now(),
+            target_time,
+            max_time,
+            adaptive: self.adaptive,
+            stability_share: self.stability_share,
+            close_visits_ratio: self.close_visits_ratio,
+            best_index: None,
+            last_best_change: Duration::ZERO,
+        }
+    }
+    fn target_time(&self, state: &UltTTT, max_time: Duration) -> Duration {
+        let legal_move_count = state.legal_move_count();
+        if legal_move_count <= 1 {
+            return Duration::ZERO;
+        }
+        // progress of game is 0.0 at start and 1.0, if all cells are occupied
+        let progress = (state.cells[0].count_ones() + state.cells[1].count_ones()) as f32 / 81.0;
+        let mut share = self.base_share + self.mid_game_share * 4.0 * progress * (1.0 - progress);
+        if legal_move_count > 9 {
+            share *= self.free_choice_factor;
+        }
+        max_time.mul_f32(share.clamp(0.0, 1.0))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TurnTimer {
+    start: Instant,
+    target_time: Duration,
+    max_time: Duration,
+    adaptive: bool,
+    stability_share: f32,
+    close_visits_ratio: f32,
+    best_index: Option<usize>,
+    last_best_change: Duration,
+}
+
+impl TurnTimer {
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+    pub fn target_time(&self) -> Duration {
+        self.target_time
+    }
+    pub fn max_time(&self) -> Duration {
+        self.max_time
+    }
+    // time until hard limit of turn
+    pub fn remaining(&self) -> Duration {
+        self.max_time.saturating_sub(self.elapsed())
+    }
+    // iterations are counted since start of turn
+    pub fn should_stop(&mut self, iterations: usize, leaders: Option<RootLeaders>) -> bool {
+        self.should_stop_after(self.elapsed(), iterations, leaders)
+    }
+    pub fn should_stop_after(
+        &mut self,
+        elapsed: Duration,
+        iterations: usize,
+        leaders: Option<RootLeaders>,
+    ) -> bool {
+        if elapsed >= self.max_time {
+            return true;
+        }
+        if !self.adaptive {
+            return false;
+        }
+        let Some(leaders) = leaders else {
+            return elapsed >= self.target_time;
+        };
+        if self.best_index != Some(leaders.best_index) {
+            self.best_index = Some(leaders.best_index);
+            self.last_best_change = elapsed;
+        }
+        // lead of best move cannot be overtaken with estimated remaining iterations
+        if iterations > 0 && !elapsed.is_zero() {
+            let remaining_iterations =
+                (self.max_time - elapsed).as_secs_f64() / elapsed.as_secs_f64() * iterations as f64;
+            let lead = leaders.best_visits.saturating_sub(leaders.second_visits);
+            if lead as f64 > remaining_iterations {
+                return true;
+            }
+        }
+        if elapsed < self.target_time {
+            return false;
+        }
+        // extend search in critical positions up to max_time
+        let is_unstable = self.last_best_change > self.target_time.mul_f32(self.stability_share);
+        let is_close =
+            leaders.second_visits as f32 >= self.close_visits_ratio * leaders.best_visits as f32;
+        !(is_unstable || is_close)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UltTTTMove;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn leaders(best_index: usize, best_visits: usize, second_visits: usize) -> Option<RootLeaders> {
+        Some(RootLeaders {
+            best_index,
+            best_visits,
+            second_visits,
+        })
+    }
+
+    #[test]
+    fn test_budget_by_phase_and_legal_moves() {
+        let time_manager = TimeManager::default();
+        let state = UltTTT::new();
+        let first_turn = time_manager.start_turn(&state, true);
+        assert_eq!(first_turn.max_time(), ms(985));
+        assert_eq!(first_turn.target_time(), ms(985));
+
+        let mv = |x: u8, y: u8| UltTTTMove::try_from((x, y)).unwrap();
+        let state = state.try_apply(&mv(4, 4)).unwrap();
+        let opening = time_manager.start_turn(&state, false);
+        assert_eq!(opening.max_time(), ms(85));
+        assert!(opening.target_time() < opening.max_time());
+        // free choice of mini board gets more time
+        let state = UltTTT::from_notation("X8/1X7/2X6/9/4O4/9/9/9/8O O X *").unwrap();
+        let free_choice = time_manager.start_turn(&state, false);
+        assert!(free_choice.target_time() > opening.target_time());
+        assert!(free_choice.target_time() <= free_choice.max_time());
+
+        // fixed time outs ignore position
+        let mut fixed = TimeManager::fixed(ms(990), ms(85)).start_turn(&state, false);
+        assert_eq!(fixed.target_time(), ms(85));
+        assert!(!fixed.should_stop_after(ms(84), 1_000, leaders(0, 1_000, 0)));
+        assert!(fixed.should_stop_after(ms(85), 1_000, leaders(0, 1_000, 0)));
+    }
+
+    #[test]
+    fn test_early_stop_and_extension() {
+        let time_manager = TimeManager::default();
+        let state = UltTTT::from_notation("X8/1X7/2X6/9/4O4/9/9/9/8O O X *").unwrap();
+        let timer = time_manager.start_turn(&state, false);
+        let target = timer.target_time();
+        let max = timer.max_time();
+
+        // 1_000 iterations in 10 ms: lead of 9_100 visits cannot be overtaken
+        let mut clear_lead = timer;
+        assert!(clear_lead.should_stop_after(ms(10), 1_000, leaders(0, 9_500, 400)));
+        // small lead: continue before target time
+        let mut small_lead = timer;
+        assert!(!small_lead.should_stop_after(ms(10), 1_000, leaders(0, 600, 400)));
+
+        // best move 0 is found early in each of following cases
+        let early_best = |mut timer: TurnTimer| {
+            assert!(!timer.should_stop_after(ms(1), 100, leaders(0, 60, 40)));
+            timer
+        };
+        // clear and stable best move: stop at target time
+        let mut stable = early_best(timer);
+        assert!(stable.should_stop_after(target, 50_000, leaders(0, 3_000, 1_000)));
+        // close best move: extend until max time
+        let mut close = early_best(timer);
+        assert!(!close.should_stop_after(target, 50_000, leaders(0, 2_000, 1_900)));
+        assert!(close.should_stop_after(max, 50_000, leaders(0, 2_000, 1_900)));
+        // best move changed late: extend
+        let mut unstable = early_best(timer);
+        assert!(!unstable.should_stop_after(target, 50_000, leaders(1, 3_000, 1_000)));
+    }
+}
@@ -0,0 +1,559 @@
+// tournament of engines: color balanced round robin or gauntlet with Bradley-Terry ratings
+// Games of a tournament are played in parallel on rayon's thread pool. Engines only search on
+// their own turn (no pondering), so each engine gets the same time independent of its opponent.
+// Ratings are fitted with Bradley-Terry model and given as Elo with 95% confidence interval.
+
+use super::{
+    ConfigRecord, GameRecord, GameRecordSink, MatchEngine, Opponent, TimeManager, UltTTT,
+    UltTTTMCTSGame, UltTTTMove,
+};
+use my_lib::my_mcts::{GameCache, MCTSGame, NoGameCache};
+use my_lib::my_tic_tac_toe::TicTacToeStatus;
+use rayon::prelude::*;
+
+use std::fmt::Display;
+
+// one virtual draw per pairing keeps ratings finite, if a player wins or loses all games
+const PRIOR_DRAWS_PER_PAIRING: f64 = 1.0;
+// quantile of normal distribution for 95% confidence interval
+const CONFIDENCE_QUANTILE: f64 = 1.96;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TournamentPlayer {
+    pub name: String,
+    pub kind: Opponent,
+}
+
+impl TournamentPlayer {
+    pub const PRESETS: [&'static str; 9] = Opponent::PRESETS;
+    pub fn new(name: impl Into<String>, kind: Opponent) -> Self {
+        TournamentPlayer {
+            name: name.into(),
+            kind,
+        }
+    }
+    // player of PRESETS with preset as name
+    pub fn preset(name: &str) -> Option<Self> {
+        Opponent::preset(name).map(|kind| TournamentPlayer::new(name, kind))
+    }
+    pub fn config_record(&self) -> ConfigRecord {
+        self.kind.config_record()
+    }
+    fn engine(&self, player: TicTacToeStatus) -> Box<dyn MatchEngine> {
+        self.kind.engine(player)
+    }
+}
+
+// first player of game plays as First and starts the game
+pub fn play_tournament_game(
+    first: &TournamentPlayer,
+    second: &TournamentPlayer,
+    time_manager: &TimeManager,
+) -> GameRecord {
+    let mut game_record = GameRecord::new(
+        TicTacToeStatus::First,
+        first.config_record(),
+        second.config_record(),
+    );
+    let mut engines = [
+        first.engine(TicTacToeStatus::First),
+        second.engine(TicTacToeStatus::Second),
+    ];
+    let mut first_turn = [true; 2];
+    let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+    let mut state = UltTTT::new();
+    while UltTTTMCTSGame::evaluate(&state, &mut game_cache).is_none() {
+        let index = if UltTTTMCTSGame::current_player(&state) == TicTacToeStatus::First {
+            0
+        } else {
+            1
+        };
+        let engine = &mut engines[index];
+        engine.update_root(&state);
+        let mut timer = time_manager.start_turn(&state, first_turn[index]);
+        first_turn[index] = false;
+        let mut iterations = 0;
+        loop {
+            engine.search_step();
+            iterations += 1;
+            if timer.should_stop(iterations, engine.leaders()) {
+                break;
+            }
+        }
+        let selected_move = engine.best_move();
+        game_record.push_move(selected_move, timer.elapsed(), iterations);
+        state = UltTTTMCTSGame::apply_move(&state, &selected_move, &mut game_cache);
+    }
+    game_record.result =
+        UltTTTMCTSGame::evaluate(&state, &mut game_cache).map(|score| score as f64);
+    game_record
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pairing {
+    // every player plays every other player
+    RoundRobin,
+    // challenger plays every other player
+    Gauntlet { challenger: usize },
+}
+
+// indices of players; first plays as First and starts the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledGame {
+    pub first: usize,
+    pub second: usize,
+}
+
+pub fn schedule_games(
+    num_players: usize,
+    pairing: Pairing,
+    games_per_pairing: usize,
+) -> Vec<ScheduledGame> {
+    // both players of a pairing start the same number of games
+    let games_per_pairing = games_per_pairing + games_per_pairing % 2;
+    let pairs: Vec<(usize, usize)> = match pairing {
+        Pairing::RoundRobin => (0..num_players)
+            .flat_map(|a| (a + 1..num_players).map(move |b| (a, b)))
+            .collect(),
+        Pairing::Gauntlet { challenger } => (0..num_players)
+            .filter(|opponent| *opponent != challenger)
+            .map(|opponent| (challenger, opponent))
+            .collect(),
+    };
+    pairs
+        .into_iter()
+        .flat_map(|(a, b)| {
+            (0..games_per_pairing).map(move |game| {
+                if game % 2 == 0 {
+                    ScheduledGame {
+                        first: a,
+                        second: b,
+                    }
+                } else {
+                    ScheduledGame {
+                        first: b,
+                        second: a,
+                    }
+                }
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct Tournament {
+    pub players: Vec<TournamentPlayer>,
+    pub pairing: Pairing,
+    // rounded up to even number of games
+    pub games_per_pairing: usize,
+    pub time_manager: TimeManager,
+}
+
+impl Tournament {
+    pub fn schedule(&self) -> Vec<ScheduledGame> {
+        schedule_games(self.players.len(), self.pairing, self.games_per_pairing)
+    }
+    // plays all scheduled games in parallel; on_game_finished is called after each game, e.g. to
+    // report progress
+    pub fn run<R, F>(&self, record_sink: R, on_game_finished: F) -> TournamentResult
+    where
+        R: GameRecordSink,
+        F: Fn(&GameResult) + Sync,
+    {
+        let games: Vec<GameResult> = self
+            .schedule()
+            .into_par_iter()
+            .map(|scheduled| {
+                let game_record = play_tournament_game(
+                    &self.players[scheduled.first],
+                    &self.players[scheduled.second],
+                    &self.time_manager,
+                );
+                let game_result = GameResult {
+                    first: scheduled.first,
+                    second: scheduled.second,
+                    score: game_record.result.unwrap_or(0.5),
+                };
+                if let Err(e) = record_sink.clone().insert(game_record) {
+                    tracing::error!(error = %e, "Failed to insert game record");
+                }
+                on_game_finished(&game_result);
+                game_result
+            })
+            .collect();
+        TournamentResult::new(
+            self.players
+                .iter()
+                .map(|player| player.name.clone())
+                .collect(),
+            games,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameResult {
+    pub first: usize,
+    pub second: usize,
+    // score from perspective of first: 1.0 win, 0.5 tie, 0.0 loss
+    pub score: f64,
+}
+
+// results of one player against one opponent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PairResult {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl PairResult {
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+    pub fn score(&self) -> f64 {
+        self.wins as f64 + 0.5 * self.draws as f64
+    }
+    pub fn push(&mut self, score: f64) {
+        match score {
+            s if s > 0.5 => self.wins += 1,
+            s if s < 0.5 => self.losses += 1,
+            _ => self.draws += 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rating {
+    pub name: String,
+    // mean elo of all players is 0
+    pub elo: f64,
+    // half width of 95% confidence interval of elo
+    pub elo_error: f64,
+    pub games: usize,
+    // share of score in all games of player
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TournamentResult {
+    pub names: Vec<String>,
+    pub games: Vec<GameResult>,
+    // in order of names
+    pub ratings: Vec<Rating>,
+}
+
+impl TournamentResult {
+    pub fn new(names: Vec<String>, games: Vec<GameResult>) -> Self {
+        let crosstable = crosstable(names.len(), &games);
+        let ratings = bradley_terry_ratings(names.len(), &games)
+            .into_iter()
+            .zip(names.iter())
+            .zip(crosstable.iter())
+            .map(|(((elo, elo_error), name), row)| {
+                let games: usize = row.iter().map(|pair| pair.games()).sum();
+                let score: f64 = row.iter().map(|pair| pair.score()).sum();
+                Rating {
+                    name: name.clone(),
+                    elo,
+                    elo_error,
+                    games,
+                    score: if games == 0 {
+                        0.0
+                    } else {
+                        score / games as f64
+                    },
+                }
+            })
+            .collect();
+        TournamentResult {
+            names,
+            games,
+            ratings,
+        }
+    }
+    // crosstable[a][b] contains results of a against b
+    pub fn crosstable(&self) -> Vec<Vec<PairResult>> {
+        crosstable(self.names.len(), &self.games)
+    }
+    // indices of players in descending order of elo
+    pub fn ranking(&self) -> Vec<usize> {
+        let mut ranking: Vec<usize> = (0..self.ratings.len()).collect();
+        ranking.sort_by(|a, b| self.ratings[*b].elo.total_cmp(&self.ratings[*a].elo));
+        ranking
+    }
+}
+
+impl Display for TournamentResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ranking = self.ranking();
+        let name_width = self
+            .names
+            .iter()
+            .map(|name| name.len())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+        writeln!(
+            f,
+            "rank {:<name_width$} {:>6} {:>6} {:>6} {:>6}",
+            "name", "elo", "+/-", "games", "score"
+        )?;
+        for (rank, index) in ranking.iter().enumerate() {
+            let rating = &self.ratings[*index];
+            writeln!(
+                f,
+                "{:>4} {:<name_width$} {:>6.0} {:>6.0} {:>6} {:>5.1}%",
+                rank + 1,
+                rating.name,
+                rating.elo,
+                rating.elo_error,
+                rating.games,
+                100.0 * rating.score
+            )?;
+        }
+        // results of row player against column player as wins-draws-losses
+        let crosstable = self.crosstable();
+        let cells: Vec<Vec<String>> = ranking
+            .iter()
+            .map(|a| {
+                ranking
+                    .iter()
+                    .map(|b| match crosstable[*a][*b] {
+                        _ if a == b => "x".to_string(),
+                        pair if pair.games() == 0 => "-".to_string(),
+                        pair => format!("{}-{}-{}", pair.wins, pair.draws, pair.losses),
+                    })
+                    .collect()
+            })
+            .collect();
+        let cell_width = cells
+            .iter()
+            .flatten()
+            .map(|cell| cell.len())
+            .max()
+            .unwrap_or(0)
+            .max(3);
+        writeln!(f)?;
+        write!(f, "     {:<name_width$}", "")?;
+        for rank in 1..=ranking.len() {
+            write!(f, " {:>cell_width$}", rank)?;
+        }
+        for (rank, (index, row)) in ranking.iter().zip(cells.iter()).enumerate() {
+            writeln!(f)?;
+            write!(f, "{:>4} {:<name_width$}", rank + 1, self.names[*index])?;
+            for cell in row.iter() {
+                write!(f, " {:>cell_width$}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn crosstable(num_players: usize, games: &[GameResult]) -> Vec<Vec<PairResult>> {
+    let mut crosstable = vec![vec![PairResult::default(); num_players]; num_players];
+    for game in games.iter() {
+        crosstable[game.first][game.second].push(game.score);
+        crosstable[game.second][game.first].push(1.0 - game.score);
+    }
+    crosstable
+}
+
+// fits Bradley-Terry model with minorization-maximization; a draw counts as half a win for both
+// players. Returns elo (mean 0) and half width of 95% confidence interval of elo for each player.
+// Confidence intervals are infinite, if players are not connected by games.
+pub fn bradley_terry_ratings(num_players: usize, games: &[GameResult]) -> Vec<(f64, f64)> {
+    // number of games and scores between players including prior
+    let mut num_games = vec![vec![0.0; num_players]; num_players];
+    let mut scores = vec![0.0; num_players];
+    for game in games.iter() {
+        num_games[game.first][game.second] += 1.0;
+        num_games[game.second][game.first] += 1.0;
+        scores[game.first] += game.score;
+        scores[game.second] += 1.0 - game.score;
+    }
+    for (row, score) in num_games.iter_mut().zip(scores.iter_mut()) {
+        for games_of_pairing in row.iter_mut().filter(|games| **games > 0.0) {
+            *games_of_pairing += PRIOR_DRAWS_PER_PAIRING;
+            *score += 0.5 * PRIOR_DRAWS_PER_PAIRING;
+        }
+    }
+
+    // strength gamma of player a wins against b with probability gamma_a / (gamma_a + gamma_b)
+    let mut gamma = vec![1.0; num_players];
+    for _ in 0..10_000 {
+        let mut max_change: f64 = 0.0;
+        for a in 0..num_players {
+            let denominator: f64 = (0..num_players)
+                .filter(|b| num_games[a][*b] > 0.0)
+                .map(|b| num_games[a][b] / (gamma[a] + gamma[b]))
+                .sum();
+            if denominator > 0.0 {
+                let new_gamma = scores[a] / denominator;
+                max_change = max_change.max((new_gamma / gamma[a]).ln().abs());
+                gamma[a] = new_gamma;
+            }
+        }
+        // geometric mean of strengths is 1, therefore mean elo is 0
+        let mean_log = gamma.iter().map(|g: &f64| g.ln()).sum::<f64>() / num_players as f64;
+        gamma.iter_mut().for_each(|g| *g /= mean_log.exp());
+        if max_change < 1e-10 {
+            break;
+        }
+    }
+
+    // fisher information of log strengths; it is singular in direction of equal shift of all
+    // strengths, which is removed by constraint of mean 0:
+    // covariance = (information + J / n)^-1 - J / n with J matrix of ones
+    let n = num_players as f64;
+    let mut information = vec![vec![1.0 / n; num_players]; num_players];
+    for a in 0..num_players {
+        for b in 0..num_players {
+            if a != b && num_games[a][b] > 0.0 {
+                let p = gamma[a] / (gamma[a] + gamma[b]);
+                let fisher = num_games[a][b] * p * (1.0 - p);
+                information[a][a] += fisher;
+                information[a][b] -= fisher;
+            }
+        }
+    }
+    let covariance = invert(information);
+
+    let elo_per_log_strength = 400.0 / std::f64::consts::LN_10;
+    (0..num_players)
+        .map(|a| {
+            let elo = elo_per_log_strength * gamma[a].ln();
+            let elo_error = match covariance.as_ref() {
+                Some(covariance) if covariance[a][a] - 1.0 / n > 0.0 => {
+                    CONFIDENCE_QUANTILE * elo_per_log_strength * (covariance[a][a] - 1.0 / n).sqrt()
+                }
+                _ => f64::INFINITY,
+            };
+            (elo, elo_error)
+        })
+        .collect()
+}
+
+// gauss-jordan elimination with partial pivoting; None, if matrix is singular
+fn invert(mut matrix: Vec<Vec<f64>>) -> Option<Vec<Vec<f64>>> {
+    let size = matrix.len();
+    let mut inverse: Vec<Vec<f64>> = (0..size)
+        .map(|row| {
+            (0..size)
+                .map(|col| if row == col { 1.0 } else { 0.0 })
+                .collect()
+        })
+        .collect();
+    for col in 0..size {
+        let pivot =
+            (col..size).max_by(|a, b| matrix[*a][col].abs().total_cmp(&matrix[*b][col].abs()))?;
+        if matrix[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+        let factor = matrix[col][col];
+        matrix[col].iter_mut().for_each(|value| *value /= factor);
+        inverse[col].iter_mut().for_each(|value| *value /= factor);
+        for row in 0..size {
+            if row != col {
+                let factor = matrix[row][col];
+                for k in 0..size {
+                    matrix[row][k] -= factor * matrix[col][k];
+                    inverse[row][k] -= factor * inverse[col][k];
+                }
+            }
+        }
+    }
+    Some(inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_schedule_is_color_balanced() {
+        let games = schedule_games(3, Pairing::RoundRobin, 2);
+        assert_eq!(games.len(), 6);
+        for a in 0..3 {
+            for b in 0..3 {
+                let count = games
+                    .iter()
+                    .filter(|game| game.first == a && game.second == b)
+                    .count();
+                assert_eq!(count, usize::from(a != b));
+            }
+        }
+
+        // odd number of games is rounded up
+        let games = schedule_games(4, Pairing::Gauntlet { challenger: 2 }, 3);
+        assert_eq!(games.len(), 12);
+        assert!(games.iter().all(|game| game.first == 2 || game.second == 2));
+        assert_eq!(games.iter().filter(|game| game.first == 2).count(), 6);
+    }
+
+    #[test]
+    fn test_bradley_terry_ratings() {
+        let game = |first, second, score| GameResult {
+            first,
+            second,
+            score,
+        };
+        // equal results give equal ratings
+        let games = vec![game(0, 1, 1.0), game(1, 0, 1.0), game(0, 1, 0.5)];
+        let ratings = bradley_terry_ratings(2, &games);
+        assert!(ratings[0].0.abs() < 1e-6 && ratings[1].0.abs() < 1e-6);
+        assert!(ratings[0].1.is_finite() && ratings[0].1 > 0.0);
+
+        // 3:1 plus virtual draw is 3.5:1.5
+        let games = vec![
+            game(0, 1, 1.0),
+            game(1, 0, 0.0),
+            game(0, 1, 1.0),
+            game(1, 0, 1.0),
+        ];
+        let ratings = bradley_terry_ratings(2, &games);
+        let expected_difference = 400.0 * (3.5f64 / 1.5).log10();
+        assert!((ratings[0].0 - ratings[1].0 - expected_difference).abs() < 1e-6);
+        assert!((ratings[0].0 + ratings[1].0).abs() < 1e-6);
+
+        // player 2 never played
+        let ratings = bradley_terry_ratings(3, &games);
+        assert!(ratings[2].1.is_infinite());
+
+        let result = TournamentResult::new(vec!["a".into(), "b".into()], games);
+        assert_eq!(result.ranking(), vec![0, 1]);
+        assert_eq!(result.ratings[0].games, 4);
+        assert_eq!(result.ratings[0].score, 0.75);
+        assert_eq!(
+            result.crosstable()[1][0],
+            PairResult {
+                wins: 1,
+                draws: 0,
+                losses: 3
+            }
+        );
+        let table = result.to_string();
+        assert!(table.contains("3-0-1"));
+        assert!(table.contains("1-0-3"));
+    }
+
+    #[test]
+    fn test_tournament_game_of_different_engines() {
+        let time_manager = TimeManager::fixed(Duration::from_millis(5), Duration::from_millis(1));
+        let players: Vec<TournamentPlayer> = TournamentPlayer::PRESETS
+            .iter()
+            .map(|name| TournamentPlayer::preset(name).unwrap())
+            .collect();
+        assert!(TournamentPlayer::preset("unknown").is_none());
+        let pure = players.iter().find(|p| p.name == "pure_mcts").unwrap();
+        for player in players.iter() {
+            let record = play_tournament_game(player, pure, &time_manager);
+            assert!(record.result.is_some());
+            let final_state = record.replay().last().unwrap().unwrap();
+            assert_ne!(final_state.get_status(), TicTacToeStatus::Vacant);
+        }
+    }
+}
@@ -1,13 +1,16 @@
 // utilities for optimization
 
 use super::{
-    HPWDefaultTTTNoGameCache, TicTacToeStatus, UltTTT, UltTTTHeuristic, UltTTTHeuristicConfig,
-    UltTTTMCTSConfig, UltTTTMCTSGame,
+    config_record, seed_match_rng, AdditionalParameters, ConfigRecord, DecidedOutcomeCutoff,
+    GameRecord, GameRecordSink, HPWDefaultTTTNoGameCache, MatchEngine, MatchRngPlayout,
+    NoGameRecordSink, Opponent, OpponentPool, PairResult, Sprt, SprtCounts, SprtDecision,
+    TicTacToeStatus, TimeManager, UltTTT, UltTTTHeuristic, UltTTTHeuristicConfig, UltTTTMCTSConfig,
+    UltTTTMCTSGame, UltTTTMove, UltTTTZobristTT,
 };
 use anyhow::Context;
 use my_lib::my_mcts::{
-    BaseConfig, BaseHeuristicConfig, CachedUTC, DynamicC, DynamicCWithExplorationBoost,
-    HeuristicCutoff, MCTSAlgo, MCTSConfig, MCTSGame, PlainMCTS, PlainTTHashMap,
+    BaseConfig, BaseHeuristicConfig, CachedUTC, DynamicC, DynamicCWithExplorationBoost, GameCache,
+    MCTSAlgo, MCTSConfig, MCTSGame, NoGameCache, NoHeuristic, PlainMCTS,
 };
 use my_lib::my_optimizer::{
     increment_progress_counter_by, update_progress, LogFormat, ObjectiveFunction, ParamBound,
@@ -15,15 +18,19 @@ use my_lib::my_optimizer::{
 };
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 use tracing::{span, Level};
 use uuid::Uuid;
 
 const TIME_OUT_TREE_BUILD_UP: Duration = Duration::from_millis(2500);
-const TIME_OUT_FIRST_TURN: Duration = Duration::from_millis(990);
 const TIME_OUT_OPP_PERSPECTIVE: Duration = Duration::from_millis(80);
-const TIME_OUT_ME_PERSPECTIVE: Duration = Duration::from_millis(85);
 const EXPECTED_NUM_NODES: usize = 200_000;
+// first turn of deterministic budgets, analog to time limits of codingame (1000 ms vs. 100 ms)
+const FIRST_TURN_FACTOR: usize = 10;
+const MAX_ITERATIONS_PER_NODE: usize = 4;
 
 pub struct EarlyBreakOff {
     pub num_check_matches: usize,
@@ -35,6 +42,193 @@ pub struct UltTTTObjectiveFunction {
     pub early_break_off: Option<EarlyBreakOff>,
     pub progress_step_size: usize,
     pub estimated_num_of_steps: usize,
+    // budget of own turns of both players
+    pub budget: MatchBudget,
+    // match i is played with seed + i; without seed each match gets a random seed
+    pub seed: Option<u64>,
+    // opponents of candidate (see OpponentPool::schedule())
+    pub opponents: OpponentPool,
+    // number of matches of one candidate, which are played in parallel; 1 plays sequentially
+    // (see match_threads())
+    pub match_threads: usize,
+    // stops evaluation, if candidate is significantly worse or better than reference score
+    pub sprt: Option<Sprt>,
+}
+
+// reason to stop evaluation of a candidate before num_matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvaluationStop {
+    EarlyBreakOff,
+    Sprt(SprtDecision),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CandidateEvaluation {
+    // mean score of played matches
+    pub score: f64,
+    pub summary: MatchSummary,
+    // log-likelihood ratio of SPRT after played matches
+    pub llr: Option<f64>,
+    pub stop: Option<EvaluationStop>,
+}
+
+impl CandidateEvaluation {
+    pub fn games(&self) -> usize {
+        self.summary.results.games()
+    }
+}
+
+// number of cores, e.g. for UltTTTObjectiveFunction::match_threads
+pub fn available_cores() -> usize {
+    std::thread::available_parallelism().map_or(1, |cores| cores.get())
+}
+
+impl UltTTTObjectiveFunction {
+    // threads, which evaluate matches of one candidate
+    // Wall time budgets must not oversubscribe cores, since matches would get less search time
+    // than on codingame. If candidates are already evaluated in parallel on rayon's thread pool,
+    // only cores not used by other candidates are available.
+    pub fn match_threads(&self) -> usize {
+        let threads = self.match_threads.clamp(1, self.num_matches.max(1));
+        if self.budget.is_deterministic() {
+            return threads;
+        }
+        let busy_cores = if rayon::current_thread_index().is_some() {
+            rayon::current_num_threads()
+        } else {
+            1
+        };
+        threads.min((available_cores() / busy_cores).max(1))
+    }
+    fn play_match(&self, config: &Config, opponent: &Opponent, index: usize) -> MatchReport {
+        update_progress(Some(self.estimated_num_of_steps), self.progress_step_size);
+        let seed = self
+            .seed
+            .map_or_else(rand::random, |seed| seed.wrapping_add(index as u64));
+        run_match(
+            config.clone(),
+            opponent,
+            index % 2 == 0,
+            &self.budget,
+            seed,
+            NoGameRecordSink {},
+        )
+    }
+    // checks, if evaluation stops after matches of summary
+    fn should_stop(&self, summary: &MatchSummary) -> Option<EvaluationStop> {
+        let count_matches = summary.results.games();
+        if count_matches >= self.num_matches {
+            return None;
+        }
+        if let Some(sprt) = self.sprt.as_ref() {
+            match sprt.decision(sprt.llr(&summary.sprt_counts)) {
+                SprtDecision::Continue => (),
+                decision => return Some(EvaluationStop::Sprt(decision)),
+            }
+        }
+        let ebo = self.early_break_off.as_ref()?;
+        if count_matches % ebo.num_check_matches != 0 {
+            return None;
+        }
+        let expected_threshold =
+            ebo.score_threshold - 0.1 * (1.0 - count_matches as f64 / self.num_matches as f64);
+        (summary.score() < expected_threshold).then_some(EvaluationStop::EarlyBreakOff)
+    }
+    // returns summary of played matches and reason, if evaluation stopped early
+    fn play_matches_sequentially(&self, config: &Config) -> (MatchSummary, Option<EvaluationStop>) {
+        let mut summary = MatchSummary::default();
+        let opponents = self.opponents.schedule(self.num_matches);
+        for (i, opponent) in opponents.into_iter().enumerate() {
+            summary.push(&self.play_match(config, opponent, i));
+            if let Some(stop) = self.should_stop(&summary) {
+                increment_progress_counter_by(self.num_matches - (i + 1));
+                return (summary, Some(stop));
+            }
+        }
+        (summary, None)
+    }
+    // Threads take next match from a shared counter. Stop rules are checked over the first
+    // completed matches in order of their index, therefore evaluation stops at the same match as
+    // in sequential evaluation. Matches, which are still running at stop, are not counted.
+    fn play_matches_in_parallel(
+        &self,
+        config: &Config,
+        threads: usize,
+    ) -> (MatchSummary, Option<EvaluationStop>) {
+        struct Progress {
+            // finished matches, which are not yet in summary
+            reports: Vec<Option<MatchReport>>,
+            // matches 0..counted are in summary
+            counted: usize,
+            summary: MatchSummary,
+            stop: Option<EvaluationStop>,
+        }
+        let opponents = self.opponents.schedule(self.num_matches);
+        let next_match = AtomicUsize::new(0);
+        let stop = AtomicBool::new(false);
+        let progress = Mutex::new(Progress {
+            reports: vec![None; self.num_matches],
+            counted: 0,
+            summary: MatchSummary::default(),
+            stop: None,
+        });
+        let span = tracing::Span::current();
+        std::thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| {
+                    let _enter = span.enter();
+                    while !stop.load(Ordering::Relaxed) {
+                        let index = next_match.fetch_add(1, Ordering::Relaxed);
+                        if index >= self.num_matches {
+                            break;
+                        }
+                        let report = self.play_match(config, opponents[index], index);
+                        let mut guard = progress.lock().expect("no thread panics with lock");
+                        let progress = &mut *guard;
+                        if progress.stop.is_some() {
+                            break;
+                        }
+                        progress.reports[index] = Some(report);
+                        while let Some(report) = progress
+                            .reports
+                            .get_mut(progress.counted)
+                            .and_then(Option::take)
+                        {
+                            progress.summary.push(&report);
+                            progress.counted += 1;
+                            progress.stop = self.should_stop(&progress.summary);
+                            if progress.stop.is_some() {
+                                stop.store(true, Ordering::Relaxed);
+                                break;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        let progress = progress.into_inner().expect("no thread panics with lock");
+        let started = next_match.load(Ordering::Relaxed).min(self.num_matches);
+        increment_progress_counter_by(self.num_matches - started);
+        (progress.summary, progress.stop)
+    }
+    // plays matches of candidate until num_matches or a stop rule applies
+    pub fn evaluate_candidate(&self, config: &Config) -> CandidateEvaluation {
+        let threads = self.match_threads();
+        let (summary, stop) = if threads > 1 {
+            self.play_matches_in_parallel(config, threads)
+        } else {
+            self.play_matches_sequentially(config)
+        };
+        CandidateEvaluation {
+            score: summary.score(),
+            summary,
+            llr: self
+                .sprt
+                .as_ref()
+                .map(|sprt| sprt.llr(&summary.sprt_counts)),
+            stop,
+        }
+    }
 }
 
 impl ObjectiveFunction for UltTTTObjectiveFunction {
@@ -66,29 +260,37 @@ impl ObjectiveFunction for UltTTTObjectiveFunction {
             }
         }
 
-        let mut sum_score: f64 = 0.0;
-        for i in 0..self.num_matches {
-            update_progress(Some(self.estimated_num_of_steps), self.progress_step_size);
-            let (score, _, _) = run_match(config.clone(), i % 2 == 0);
-            sum_score += score;
-            if let Some(ref ebo) = self.early_break_off {
-                let count_matches = i + 1;
-                if count_matches % ebo.num_check_matches == 0 && count_matches < self.num_matches {
-                    let early_score = sum_score / (count_matches as f64);
-                    let expected_threshold = ebo.score_threshold
-                        - 0.1 * (1.0 - count_matches as f64 / self.num_matches as f64);
-                    if early_score < expected_threshold {
-                        increment_progress_counter_by(self.num_matches - count_matches);
-                        tracing::debug!(eval_id, early_score, "Evaluation early cut-off.");
-                        return Ok(early_score);
-                    }
-                }
-            }
+        let evaluation = self.evaluate_candidate(&config);
+        let summary = &evaluation.summary;
+        let score = evaluation.score;
+        if evaluation.stop == Some(EvaluationStop::EarlyBreakOff) {
+            tracing::debug!(
+                eval_id,
+                early_score = score,
+                games = evaluation.games(),
+                wins = summary.results.wins,
+                draws = summary.results.draws,
+                losses = summary.results.losses,
+                "Evaluation early cut-off."
+            );
+            return Ok(score);
         }
 
-        let score = sum_score / self.num_matches as f64;
-
-        tracing::debug!(eval_id, score, "Evaluation completed.");
+        tracing::debug!(
+            eval_id,
+            score,
+            games = evaluation.games(),
+            llr = evaluation.llr,
+            sprt = ?evaluation.stop,
+            wins = summary.results.wins,
+            draws = summary.results.draws,
+            losses = summary.results.losses,
+            iterations_per_turn = summary.heuristic.iterations_per_turn(),
+            opponent_iterations_per_turn = summary.opponent.iterations_per_turn(),
+            max_tree_size = summary.heuristic.max_tree_size,
+            root_resets = summary.heuristic.root_resets,
+            "Evaluation completed."
+        );
 
         Ok(score)
     }
@@ -99,33 +301,301 @@ pub type UltTTTMCTSFirst = PlainMCTS<
     UltTTTHeuristic,
     UltTTTMCTSConfig,
     CachedUTC,
-    PlainTTHashMap<UltTTT>,
+    UltTTTZobristTT,
     DynamicCWithExplorationBoost,
     HPWDefaultTTTNoGameCache,
-    HeuristicCutoff,
+    MatchRngPlayout<DecidedOutcomeCutoff>,
 >;
 pub type UltTTTMCTSSecond = PlainMCTS<
     UltTTTMCTSGame,
     UltTTTHeuristic,
     UltTTTMCTSConfig,
     CachedUTC,
-    PlainTTHashMap<UltTTT>,
+    UltTTTZobristTT,
     DynamicC,
     HPWDefaultTTTNoGameCache,
-    HeuristicCutoff,
+    MatchRngPlayout<DecidedOutcomeCutoff>,
 >;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchBudget {
+    // timing of codingame: tree build up, own turns by time manager and pondering on turns of
+    // opponent; results depend on machine load
+    WallTime(TimeManager),
+    // fixed number of iterations of each own turn
+    Iterations(usize),
+    // fixed number of new tree nodes of each own turn
+    Nodes(usize),
+}
+
+impl Default for MatchBudget {
+    fn default() -> Self {
+        MatchBudget::WallTime(TimeManager::default())
+    }
+}
+
+impl MatchBudget {
+    // deterministic budgets replay the same game for the same seed; they have neither tree build
+    // up nor pondering, and first turn gets FIRST_TURN_FACTOR times budget of a turn
+    pub fn is_deterministic(&self) -> bool {
+        !matches!(self, MatchBudget::WallTime(_))
+    }
+}
+
+// searches own turn within budget and returns number of iterations
+fn search_turn(
+    engine: &mut dyn MatchEngine,
+    state: &UltTTT,
+    budget: &MatchBudget,
+    first_turn: bool,
+) -> usize {
+    let factor = if first_turn { FIRST_TURN_FACTOR } else { 1 };
+    let mut iterations = 0;
+    match budget {
+        MatchBudget::WallTime(time_manager) => {
+            let mut timer = time_manager.start_turn(state, first_turn);
+            loop {
+                engine.search_step();
+                iterations += 1;
+                if timer.should_stop(iterations, engine.leaders()) {
+                    break;
+                }
+            }
+        }
+        MatchBudget::Iterations(turn_iterations) => {
+            while iterations < factor * (*turn_iterations).max(1) {
+                engine.search_step();
+                iterations += 1;
+            }
+        }
+        MatchBudget::Nodes(turn_nodes) => {
+            // solved parts of tree do not grow, therefore iterations are limited, too
+            let max_nodes = engine.num_nodes() + factor * turn_nodes;
+            let max_iterations = factor * (*turn_nodes).max(1) * MAX_ITERATIONS_PER_NODE;
+            while engine.num_nodes() < max_nodes && iterations < max_iterations {
+                engine.search_step();
+                iterations += 1;
+            }
+            if iterations == 0 {
+                engine.search_step();
+                iterations += 1;
+            }
+        }
+    }
+    iterations
+}
+
+// result of a match from perspective of heuristic player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchResult {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl MatchResult {
+    pub fn from_score(score: f64) -> Self {
+        match score {
+            s if s > 0.5 => MatchResult::Win,
+            s if s < 0.5 => MatchResult::Loss,
+            _ => MatchResult::Draw,
+        }
+    }
+    pub fn score(&self) -> f64 {
+        match self {
+            MatchResult::Win => 1.0,
+            MatchResult::Draw => 0.5,
+            MatchResult::Loss => 0.0,
+        }
+    }
+}
+
+// one ply of a match
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TurnReport {
+    // First is heuristic player, Second is opponent
+    pub player: TicTacToeStatus,
+    pub mv: UltTTTMove,
+    pub iterations: usize,
+    // number of nodes of tree of player after search
+    pub tree_size: usize,
+    pub time: Duration,
+}
+
+// search statistics of one player of a match or summed over matches
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PlayerStatistics {
+    pub turns: usize,
+    // iterations of own turns without tree build up and pondering
+    pub iterations: usize,
+    pub max_tree_size: usize,
+    // results of set_root() after first ply of match: tree was reused or reset
+    pub root_reuses: usize,
+    pub root_resets: usize,
+    // time of own turns
+    pub time: Duration,
+}
+
+impl PlayerStatistics {
+    pub fn iterations_per_turn(&self) -> f64 {
+        self.iterations as f64 / self.turns.max(1) as f64
+    }
+    pub fn add(&mut self, other: &PlayerStatistics) {
+        self.turns += other.turns;
+        self.iterations += other.iterations;
+        self.max_tree_size = self.max_tree_size.max(other.max_tree_size);
+        self.root_reuses += other.root_reuses;
+        self.root_resets += other.root_resets;
+        self.time += other.time;
+    }
+    fn push_root_update(&mut self, reused: bool) {
+        if reused {
+            self.root_reuses += 1;
+        } else {
+            self.root_resets += 1;
+        }
+    }
+}
+
+impl Display for PlayerStatistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.0} iterations/turn, {:.1} ms/turn, max tree size {}, root reused {} reset {}",
+            self.iterations_per_turn(),
+            self.time.as_secs_f64() * 1_000.0 / self.turns.max(1) as f64,
+            self.max_tree_size,
+            self.root_reuses,
+            self.root_resets
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchReport {
+    pub heuristic_is_start_player: bool,
+    pub seed: u64,
+    pub result: MatchResult,
+    pub turns: Vec<TurnReport>,
+    pub heuristic: PlayerStatistics,
+    pub opponent: PlayerStatistics,
+    // wall time of match including tree build up and pondering
+    pub time: Duration,
+}
+
+impl MatchReport {
+    pub fn score(&self) -> f64 {
+        self.result.score()
+    }
+}
+
+// trees of both players at end of match, see run_match_with_trees()
+pub struct MatchTrees {
+    pub heuristic: UltTTTMCTSFirst,
+    // see MatchEngine::into_any() to analyze tree of opponent
+    pub opponent: Box<dyn MatchEngine>,
+}
+
+// W/D/L and search statistics of several matches
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MatchSummary {
+    pub results: PairResult,
+    // results in order of matches, e.g. for SPRT
+    pub sprt_counts: SprtCounts,
+    pub heuristic: PlayerStatistics,
+    pub opponent: PlayerStatistics,
+    pub time: Duration,
+}
+
+impl MatchSummary {
+    pub fn push(&mut self, report: &MatchReport) {
+        self.results.push(report.score());
+        self.sprt_counts.push(report.score());
+        self.heuristic.add(&report.heuristic);
+        self.opponent.add(&report.opponent);
+        self.time += report.time;
+    }
+    pub fn score(&self) -> f64 {
+        self.results.score() / self.results.games().max(1) as f64
+    }
+}
+
+impl<'a> FromIterator<&'a MatchReport> for MatchSummary {
+    fn from_iter<I: IntoIterator<Item = &'a MatchReport>>(iter: I) -> Self {
+        let mut summary = MatchSummary::default();
+        for report in iter {
+            summary.push(report);
+        }
+        summary
+    }
+}
+
+impl Display for MatchSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "W-D-L {}-{}-{}, score {:.3}, time {:.1} s",
+            self.results.wins,
+            self.results.draws,
+            self.results.losses,
+            self.score(),
+            self.time.as_secs_f64()
+        )?;
+        writeln!(f, "heuristic: {}", self.heuristic)?;
+        write!(f, "opponent:  {}", self.opponent)
+    }
+}
+
 // structure of run_match() tries to represent timing on codingame, which was measured with debug messages
 // 1.) first turn: long time out
 // 2.) than iterate from perspective of opponent (about 70 ms)
-// 3.) than iterate frm my perspective (about 70 ms)
+// 3.) than iterate frm my perspective (time of turn is given by time_manager)
 // 4.) if not terminal, go to 2.)
 // Since we have here two MCTS players, both players get same timings
+// With a deterministic budget only own turns are searched (see MatchBudget). Random numbers of
+// both players are drawn from match rng seeded with seed.
+// Heuristic player always plays as First; opponent plays as Second.
+
+pub fn run_match<R: GameRecordSink>(
+    config: Config,
+    opponent: &Opponent,
+    heuristic_is_start_player: bool,
+    budget: &MatchBudget,
+    seed: u64,
+    record_sink: R,
+) -> MatchReport {
+    run_match_with_trees(
+        config,
+        opponent,
+        heuristic_is_start_player,
+        budget,
+        seed,
+        record_sink,
+    )
+    .0
+}
 
-pub fn run_match(
+// same as run_match(), but returns trees of both players, e.g. to analyze them
+pub fn run_match_with_trees<R: GameRecordSink>(
     config: Config,
+    opponent: &Opponent,
     heuristic_is_start_player: bool,
-) -> (f64, UltTTTMCTSFirst, UltTTTMCTSSecond) {
+    budget: &MatchBudget,
+    seed: u64,
+    mut record_sink: R,
+) -> (MatchReport, MatchTrees) {
+    let match_start = Instant::now();
+    let _match_rng = seed_match_rng(seed);
+    let mut game_record = GameRecord::new(
+        if heuristic_is_start_player {
+            TicTacToeStatus::First
+        } else {
+            TicTacToeStatus::Second
+        },
+        config.parameter_map(),
+        opponent.config_record(),
+    );
+
     // Initial config without exploration_boost
     let mut initial_config = config.mcts.clone();
     initial_config.base_config.exploration_boost = [
@@ -136,133 +606,127 @@ pub fn run_match(
 
     let mut first_mcts_ult_ttt: UltTTTMCTSFirst =
         PlainMCTS::new(initial_config, config.heuristic, EXPECTED_NUM_NODES);
-    let mut first_ult_ttt_game_data = UltTTT::new();
-    first_mcts_ult_ttt.set_root(&first_ult_ttt_game_data);
-    let mut first_time_out = TIME_OUT_FIRST_TURN;
-    let mut second_mcts_ult_ttt: UltTTTMCTSSecond = PlainMCTS::new(
-        UltTTTMCTSConfig::new_optimized(),
-        UltTTTHeuristicConfig::new_optimized(),
-        EXPECTED_NUM_NODES,
-    );
-    let mut second_ult_ttt_game_data = UltTTT::new();
-    second_mcts_ult_ttt.set_root(&second_ult_ttt_game_data);
-    let mut second_time_out = TIME_OUT_FIRST_TURN;
+    let mut ult_ttt_game_data = UltTTT::new();
+    let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+    first_mcts_ult_ttt.set_root(&ult_ttt_game_data);
+    let mut first_turn_of_first = true;
+    let mut second_engine = opponent.engine(TicTacToeStatus::Second);
+    second_engine.update_root(&ult_ttt_game_data);
+    let mut first_turn_of_second = true;
+    // statistics of first and second
+    let mut statistics = [PlayerStatistics::default(); 2];
+    let mut turns: Vec<TurnReport> = Vec::new();
 
     // player first is always heuristic player, but only every second game start player
     let mut first = if heuristic_is_start_player {
         true
     } else {
-        first_ult_ttt_game_data.set_current_player(TicTacToeStatus::Second);
-        second_ult_ttt_game_data.set_current_player(TicTacToeStatus::Second);
+        ult_ttt_game_data.set_current_player(TicTacToeStatus::Second);
         false
     };
 
     // initial tree build up before codingame sends first initial input
-    // first first
-    let start = Instant::now();
-    while start.elapsed() < TIME_OUT_TREE_BUILD_UP {
-        first_mcts_ult_ttt.iterate();
+    if !budget.is_deterministic() {
+        // first first
+        let start = Instant::now();
+        while start.elapsed() < TIME_OUT_TREE_BUILD_UP {
+            first_mcts_ult_ttt.iterate();
+        }
+        // second second
+        let start = Instant::now();
+        while start.elapsed() < TIME_OUT_TREE_BUILD_UP {
+            second_engine.search_step();
+        }
     }
     // apply exploration boost to config of first
     first_mcts_ult_ttt.mcts_config.base_config.exploration_boost =
         config.mcts.base_config.exploration_boost;
-    // second second
-    let start = Instant::now();
-    while start.elapsed() < TIME_OUT_TREE_BUILD_UP {
-        second_mcts_ult_ttt.iterate();
-    }
 
     let mut turn_counter = 0;
-    while UltTTTMCTSGame::evaluate(&first_ult_ttt_game_data, &mut first_mcts_ult_ttt.game_cache)
-        .is_none()
-    {
-        if first {
-            // iterate first tree from first perspective
-            if turn_counter > 0 && !first_mcts_ult_ttt.set_root(&first_ult_ttt_game_data) {
+    while UltTTTMCTSGame::evaluate(&ult_ttt_game_data, &mut game_cache).is_none() {
+        let (engine, first_turn, player, name): (&mut dyn MatchEngine, &mut bool, _, &str) =
+            if first {
+                (
+                    &mut first_mcts_ult_ttt,
+                    &mut first_turn_of_first,
+                    TicTacToeStatus::First,
+                    "first",
+                )
+            } else {
+                (
+                    second_engine.as_mut(),
+                    &mut first_turn_of_second,
+                    TicTacToeStatus::Second,
+                    "second",
+                )
+            };
+        let player_statistics = &mut statistics[usize::from(!first)];
+        // iterate tree from own perspective
+        if turn_counter > 0 {
+            let reused = engine.update_root(&ult_ttt_game_data);
+            player_statistics.push_root_update(reused);
+            if !reused {
                 tracing::debug!(
                     heuristic_is_start_player,
                     turn_counter,
-                    "Reset tree root of first."
+                    "Reset tree root of {}.",
+                    name
                 );
             }
+        }
+        let start = Instant::now();
+        let iterations = search_turn(engine, &ult_ttt_game_data, budget, *first_turn);
+        *first_turn = false;
+        let selected_move = engine.best_move();
+        let turn = TurnReport {
+            player,
+            mv: selected_move,
+            iterations,
+            tree_size: engine.num_nodes(),
+            time: start.elapsed(),
+        };
+        player_statistics.turns += 1;
+        player_statistics.iterations += iterations;
+        player_statistics.max_tree_size = player_statistics.max_tree_size.max(turn.tree_size);
+        player_statistics.time += turn.time;
+        game_record.push_move(selected_move, turn.time, iterations);
+        turns.push(turn);
+        ult_ttt_game_data =
+            UltTTTMCTSGame::apply_move(&ult_ttt_game_data, &selected_move, &mut game_cache);
+        if !budget.is_deterministic()
+            && UltTTTMCTSGame::evaluate(&ult_ttt_game_data, &mut game_cache).is_none()
+        {
+            // if not terminal, iterate tree from perspective of opponent
+            let reused = engine.update_root(&ult_ttt_game_data);
+            player_statistics.push_root_update(reused);
             let start = Instant::now();
-            while start.elapsed() < first_time_out {
-                first_mcts_ult_ttt.iterate();
-            }
-            first_time_out = TIME_OUT_ME_PERSPECTIVE;
-            let selected_move = *first_mcts_ult_ttt.select_move();
-            first_ult_ttt_game_data = UltTTTMCTSGame::apply_move(
-                &first_ult_ttt_game_data,
-                &selected_move,
-                &mut first_mcts_ult_ttt.game_cache,
-            );
-            second_ult_ttt_game_data = UltTTTMCTSGame::apply_move(
-                &second_ult_ttt_game_data,
-                &selected_move,
-                &mut second_mcts_ult_ttt.game_cache,
-            );
-            if UltTTTMCTSGame::evaluate(
-                &first_ult_ttt_game_data,
-                &mut first_mcts_ult_ttt.game_cache,
-            )
-            .is_none()
-            {
-                // if not terminal, iterate first tree from second perspective
-                first_mcts_ult_ttt.set_root(&first_ult_ttt_game_data);
-                let start = Instant::now();
-                while start.elapsed() < TIME_OUT_OPP_PERSPECTIVE {
-                    first_mcts_ult_ttt.iterate();
-                }
-            }
-            first = false;
-        } else {
-            // iterate second tree from second perspective
-            if turn_counter > 0 && !second_mcts_ult_ttt.set_root(&second_ult_ttt_game_data) {
-                tracing::debug!(
-                    heuristic_is_start_player,
-                    turn_counter,
-                    "Reset tree root of second."
-                );
-            }
-            let start = Instant::now();
-            while start.elapsed() < second_time_out {
-                second_mcts_ult_ttt.iterate();
-            }
-            second_time_out = TIME_OUT_ME_PERSPECTIVE;
-            let selected_move = *second_mcts_ult_ttt.select_move();
-            second_ult_ttt_game_data = UltTTTMCTSGame::apply_move(
-                &second_ult_ttt_game_data,
-                &selected_move,
-                &mut second_mcts_ult_ttt.game_cache,
-            );
-            first_ult_ttt_game_data = UltTTTMCTSGame::apply_move(
-                &first_ult_ttt_game_data,
-                &selected_move,
-                &mut first_mcts_ult_ttt.game_cache,
-            );
-            if UltTTTMCTSGame::evaluate(
-                &first_ult_ttt_game_data,
-                &mut first_mcts_ult_ttt.game_cache,
-            )
-            .is_none()
-            {
-                // if not terminal, iterate second tree from first perspective
-                let start = Instant::now();
-                second_mcts_ult_ttt.set_root(&second_ult_ttt_game_data);
-                while start.elapsed() < TIME_OUT_OPP_PERSPECTIVE {
-                    second_mcts_ult_ttt.iterate();
-                }
+            while start.elapsed() < TIME_OUT_OPP_PERSPECTIVE {
+                engine.search_step();
             }
-            first = true;
         }
+        first = !first;
         turn_counter += 1;
     }
-    (
-        UltTTTMCTSGame::evaluate(&first_ult_ttt_game_data, &mut first_mcts_ult_ttt.game_cache)
-            .unwrap() as f64,
-        first_mcts_ult_ttt,
-        second_mcts_ult_ttt,
-    )
+    let score = UltTTTMCTSGame::evaluate(&ult_ttt_game_data, &mut game_cache).unwrap() as f64;
+    game_record.result = Some(score);
+    if let Err(e) = record_sink.insert(game_record) {
+        tracing::error!(error = %e, "Failed to insert game record");
+    }
+    let [heuristic, opponent] = statistics;
+    let report = MatchReport {
+        heuristic_is_start_player,
+        seed,
+        result: MatchResult::from_score(score),
+        turns,
+        heuristic,
+        opponent,
+        time: match_start.elapsed(),
+    };
+    let trees = MatchTrees {
+        heuristic: first_mcts_ult_ttt,
+        opponent: second_engine,
+    };
+    (report, trees)
 }
 
 #[derive(Debug, Clone, Default, PartialEq)]
@@ -346,22 +810,29 @@ impl From<Config> for Vec<f64> {
     }
 }
 
+// Config::parameter_names() holds names of parameters of UltTTTHeuristicConfig, which are not
+// defined by MCTSConfig and HeuristicConfig
+impl AdditionalParameters for UltTTTHeuristicConfig {
+    fn additional_parameters(&self) -> Vec<(String, f64)> {
+        let config = Config {
+            mcts: UltTTTMCTSConfig::default(),
+            heuristic: *self,
+        };
+        let generic_parameters = config_record(&config.mcts, &NoHeuristic {});
+        config
+            .parameter_map()
+            .into_iter()
+            .filter(|(name, _)| !generic_parameters.contains_key(name))
+            .collect()
+    }
+}
+
 impl Serialize for Config {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let values: Vec<f64> = self.clone().into();
-        let names = Config::parameter_names();
-
-        if names.len() != values.len() {
-            return Err(serde::ser::Error::custom(
-                "Mismatched config name/value length",
-            ));
-        }
-
-        let map: BTreeMap<_, _> = names.into_iter().zip(values).collect();
-        map.serialize(serializer)
+        self.parameter_map().serialize(serializer)
     }
 }
 
@@ -380,6 +851,10 @@ impl<'de> Deserialize<'de> for Config {
 }
 
 impl Config {
+    pub fn parameter_map(&self) -> ConfigRecord {
+        let values: Vec<f64> = self.clone().into();
+        Config::parameter_names().into_iter().zip(values).collect()
+    }
     pub fn parameter_names() -> Vec<String> {
         vec![
             "exploration_constant".into(),
@@ -505,6 +980,7 @@ impl Config {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ChannelGameRecordSink;
 
     #[test]
     fn test_config_serialization() {
@@ -514,6 +990,163 @@ mod tests {
         assert_eq!(config, deserialized);
     }
 
+    #[test]
+    fn test_seeded_match_with_deterministic_budget_replays() {
+        let play = |budget: MatchBudget, seed: u64| {
+            let (tx, rx) = crossbeam::channel::unbounded();
+            let report = run_match(
+                Config::default(),
+                &Opponent::default(),
+                seed % 2 == 0,
+                &budget,
+                seed,
+                ChannelGameRecordSink::new(tx),
+            );
+            let record = rx.recv().unwrap();
+            assert_eq!(record.result, Some(report.score()));
+            record
+        };
+        for budget in [MatchBudget::Iterations(30), MatchBudget::Nodes(30)] {
+            let record = play(budget, 1);
+            let replay = play(budget, 1);
+            let moves = |record: &GameRecord| -> Vec<(u8, u8, usize)> {
+                record
+                    .moves
+                    .iter()
+                    .map(|mv| (mv.row, mv.col, mv.iterations))
+                    .collect()
+            };
+            assert_eq!(moves(&record), moves(&replay));
+            assert_eq!(record.result, replay.result);
+            // first turn of each player gets FIRST_TURN_FACTOR times budget
+            if budget == MatchBudget::Iterations(30) {
+                assert_eq!(record.moves[0].iterations, 300);
+                assert_eq!(record.moves[2].iterations, 30);
+            }
+            let other = play(budget, 3);
+            assert_ne!(moves(&record), moves(&other));
+        }
+    }
+
+    #[test]
+    fn test_match_report_matches_trees() {
+        let (report, trees) = run_match_with_trees(
+            Config::default(),
+            &Opponent::Random,
+            false,
+            &MatchBudget::Iterations(20),
+            5,
+            NoGameRecordSink {},
+        );
+        assert_eq!(report.turns[0].player, TicTacToeStatus::Second);
+        assert_eq!(report.turns[0].iterations, 20 * FIRST_TURN_FACTOR);
+        let turns = |player| {
+            report
+                .turns
+                .iter()
+                .filter(move |turn| turn.player == player)
+        };
+        let heuristic_turns: Vec<&TurnReport> = turns(TicTacToeStatus::First).collect();
+        assert_eq!(report.heuristic.turns, heuristic_turns.len());
+        assert_eq!(
+            report.heuristic.iterations,
+            heuristic_turns
+                .iter()
+                .map(|turn| turn.iterations)
+                .sum::<usize>()
+        );
+        // every own turn after first ply of match updates root
+        assert_eq!(
+            report.heuristic.root_reuses + report.heuristic.root_resets,
+            report.heuristic.turns
+        );
+        assert_eq!(
+            report.opponent.root_reuses + report.opponent.root_resets,
+            report.opponent.turns - 1
+        );
+        // tree of heuristic player is not searched after its last turn
+        assert_eq!(
+            trees.heuristic.tree.nodes.len(),
+            heuristic_turns.last().unwrap().tree_size
+        );
+        assert_eq!(report.opponent.max_tree_size, 0);
+        assert_eq!(
+            report.opponent.turns,
+            turns(TicTacToeStatus::Second).count()
+        );
+
+        let summary: MatchSummary = [&report, &report].into_iter().collect();
+        assert_eq!(summary.results.games(), 2);
+        assert_eq!(summary.score(), report.score());
+        assert_eq!(summary.heuristic.turns, 2 * report.heuristic.turns);
+    }
+
+    #[test]
+    fn test_parallel_evaluation_equals_sequential_evaluation() {
+        let objective_function = |match_threads, score_threshold| UltTTTObjectiveFunction {
+            num_matches: 8,
+            early_break_off: Some(EarlyBreakOff {
+                num_check_matches: 4,
+                score_threshold,
+            }),
+            progress_step_size: 1,
+            estimated_num_of_steps: 8,
+            budget: MatchBudget::Iterations(5),
+            seed: Some(11),
+            opponents: OpponentPool::new(Opponent::Random),
+            match_threads,
+            sprt: None,
+        };
+        // without cut-off and with cut-off after 4 matches
+        for score_threshold in [0.0, 2.0] {
+            let sequential = objective_function(1, score_threshold);
+            let parallel = objective_function(3, score_threshold);
+            assert_eq!(parallel.match_threads(), 3);
+            let config = Config::default();
+            assert_eq!(
+                sequential.evaluate(config.clone()).unwrap(),
+                parallel.evaluate(config).unwrap()
+            );
+        }
+        // wall time budgets do not oversubscribe cores
+        let mut wall_time = objective_function(available_cores() + 1, 0.0);
+        wall_time.budget = MatchBudget::default();
+        wall_time.num_matches = available_cores() + 1;
+        assert!(wall_time.match_threads() <= available_cores());
+    }
+
+    #[test]
+    fn test_sprt_stops_evaluation() {
+        let objective_function = |match_threads| UltTTTObjectiveFunction {
+            num_matches: 40,
+            early_break_off: None,
+            progress_step_size: 1,
+            estimated_num_of_steps: 40,
+            budget: MatchBudget::Iterations(5),
+            seed: Some(11),
+            opponents: OpponentPool::new(Opponent::Random),
+            match_threads,
+            sprt: Some(Sprt::new(0.5, 0.6)),
+        };
+        let config = Config::default();
+        let sequential = objective_function(1).evaluate_candidate(&config);
+        // random opponent is clearly weaker
+        assert_eq!(
+            sequential.stop,
+            Some(EvaluationStop::Sprt(SprtDecision::AcceptH1))
+        );
+        assert!(sequential.games() < 40);
+        // pentanomial model decides only after complete pairs
+        assert_eq!(sequential.games() % 2, 0);
+        assert_eq!(sequential.score, sequential.summary.score());
+        // timings differ between runs
+        let parallel = objective_function(3).evaluate_candidate(&config);
+        assert_eq!(parallel.stop, sequential.stop);
+        assert_eq!(parallel.llr, sequential.llr);
+        assert_eq!(parallel.summary.results, sequential.summary.results);
+        assert_eq!(parallel.summary.sprt_counts, sequential.summary.sprt_counts);
+    }
+
     #[test]
     fn test_config_bounds() {
         let lower = Config::lower_bounds();
@@ -0,0 +1,185 @@
+// zobrist hashing of UltTTT
+
+use super::{NextActionConstraint, UltTTT, UltTTTMove};
+use my_lib::my_mcts::{HeuristicCache, TranspositionTable};
+
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+pub struct ZobristKeys {
+    pub cells: [[u64; 81]; 2],
+    // MiniBoard(0..=8), Init, None
+    pub constraints: [u64; 11],
+    pub second_to_move: u64,
+}
+
+pub const ZOBRIST_KEYS: ZobristKeys = ZobristKeys::new(0x2545_F491_4F6C_DD1D);
+
+impl ZobristKeys {
+    const fn new(seed: u64) -> Self {
+        let mut keys = ZobristKeys {
+            cells: [[0; 81]; 2],
+            constraints: [0; 11],
+            second_to_move: 0,
+        };
+        let mut state = seed;
+        let mut player = 0;
+        while player < 2 {
+            let mut cell = 0;
+            while cell < 81 {
+                state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+                keys.cells[player][cell] = Self::split_mix_64(state);
+                cell += 1;
+            }
+            player += 1;
+        }
+        let mut constraint = 0;
+        while constraint < 11 {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            keys.constraints[constraint] = Self::split_mix_64(state);
+            constraint += 1;
+        }
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        keys.second_to_move = Self::split_mix_64(state);
+        keys
+    }
+    const fn split_mix_64(state: u64) -> u64 {
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    pub fn constraint(&self, constraint: NextActionConstraint) -> u64 {
+        match constraint {
+            NextActionConstraint::MiniBoard(status_index) => {
+                self.constraints[usize::from(status_index)]
+            }
+            NextActionConstraint::Init => self.constraints[9],
+            NextActionConstraint::None => self.constraints[10],
+        }
+    }
+}
+
+// zobrist keys are already uniformly distributed, therefore they can be used directly as hash
+#[derive(Default)]
+pub struct ZobristHasher {
+    hash: u64,
+}
+
+impl Hasher for ZobristHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.hash = self.hash.rotate_left(8) ^ byte as u64;
+        }
+    }
+    fn write_u64(&mut self, key: u64) {
+        self.hash = key;
+    }
+}
+
+pub type ZobristHashMap<V> = HashMap<u64, V, BuildHasherDefault<ZobristHasher>>;
+
+// transposition table, which only stores zobrist key of state
+// WARNING: hash collisions of two different states are not detected.
+#[derive(Clone)]
+pub struct UltTTTZobristTT {
+    pub table: ZobristHashMap<usize>,
+}
+
+impl TranspositionTable<UltTTT, usize> for UltTTTZobristTT {
+    fn new(expected_num_nodes: usize) -> Self {
+        UltTTTZobristTT {
+            table: ZobristHashMap::with_capacity_and_hasher(expected_num_nodes, Default::default()),
+        }
+    }
+    fn get(&self, state: &UltTTT) -> Option<&usize> {
+        self.table.get(&state.zobrist())
+    }
+    fn insert(&mut self, state: UltTTT, value: usize) {
+        self.table.insert(state.zobrist(), value);
+    }
+    fn clear(&mut self) {
+        self.table.clear();
+    }
+}
+
+// heuristic cache, which only stores zobrist key of state
+#[derive(Clone)]
+pub struct UltTTTZobristHeuristicCache {
+    pub cache: ZobristHashMap<f32>,
+}
+
+impl HeuristicCache<UltTTT, UltTTTMove> for UltTTTZobristHeuristicCache {
+    fn new() -> Self {
+        UltTTTZobristHeuristicCache {
+            cache: ZobristHashMap::default(),
+        }
+    }
+    fn get_intermediate_score(&self, state: &UltTTT) -> Option<f32> {
+        self.cache.get(&state.zobrist()).cloned()
+    }
+    fn insert_intermediate_score(&mut self, state: &UltTTT, score: f32) {
+        self.cache.insert(state.zobrist(), score);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UltTTTMCTSGame;
+    use my_lib::my_mcts::{GameCache, MCTSGame, NoGameCache};
+    use my_lib::my_tic_tac_toe::TicTacToeStatus;
+    use rand::prelude::SliceRandom;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_zobrist_keys_are_unique() {
+        let mut keys: HashSet<u64> = HashSet::new();
+        for player_keys in ZOBRIST_KEYS.cells.iter() {
+            keys.extend(player_keys.iter());
+        }
+        keys.extend(ZOBRIST_KEYS.constraints.iter());
+        keys.insert(ZOBRIST_KEYS.second_to_move);
+        assert_eq!(keys.len(), 2 * 81 + 11 + 1);
+    }
+
+    #[test]
+    fn test_incremental_zobrist_matches_full_computation() {
+        let mut rng = rand::thread_rng();
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        for i in 0..500 {
+            let mut state = UltTTT::new();
+            if i % 2 == 1 {
+                state.set_current_player(TicTacToeStatus::Second);
+            }
+            assert_eq!(state.zobrist(), state.calc_zobrist());
+            while UltTTTMCTSGame::evaluate(&state, &mut game_cache).is_none() {
+                let moves: Vec<UltTTTMove> = UltTTTMCTSGame::available_moves(&state).collect();
+                let mv = moves.choose(&mut rng).unwrap();
+                state = UltTTTMCTSGame::apply_move(&state, mv, &mut game_cache);
+                assert_eq!(state.zobrist(), state.calc_zobrist());
+            }
+        }
+    }
+
+    #[test]
+    fn test_transpositions_share_zobrist_key() {
+        let mut game_cache: NoGameCache<UltTTT, UltTTTMove> = NoGameCache::new();
+        // both move orders end with same cells, constraint and side to move
+        let play = |moves: &[(u8, u8)], game_cache: &mut NoGameCache<UltTTT, UltTTTMove>| {
+            moves.iter().fold(UltTTT::new(), |state, &cg_coordinates| {
+                let mv = UltTTTMove::try_from(cg_coordinates).unwrap();
+                UltTTTMCTSGame::apply_move(&state, &mv, game_cache)
+            })
+        };
+        let first = play(&[(3, 3), (1, 1), (3, 5), (1, 7)], &mut game_cache);
+        let second = play(&[(3, 5), (1, 7), (3, 3), (1, 1)], &mut game_cache);
+        let third = play(&[(3, 3), (1, 1), (3, 5), (1, 6)], &mut game_cache);
+        assert!(first == second);
+        assert_eq!(first.zobrist(), second.zobrist());
+        assert_ne!(first.zobrist(), third.zobrist());
+    }
+}